@@ -0,0 +1,134 @@
+//! Generate built-in registry entries from Anchor IDLs or Rust error enums.
+//!
+//! ```bash
+//! # From an Anchor IDL (the program ID is read from the IDL if it has one)
+//! cargo run --example gen_registry -- idl whirlpool.json --merge data/errors.json
+//!
+//! # From a native program's error enum
+//! cargo run --example gen_registry -- rust token-2022/src/error.rs --enum TokenError \
+//!     --program-id TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb --name spl-token-2022 \
+//!     --merge data/errors.json
+//! ```
+//!
+//! Without `--merge`, the generated entries are printed as an `errors.json`
+//! fragment, which also works as an `--errors-db` file.
+
+use anyhow::{bail, Context};
+use clap::{Parser, Subcommand};
+use ohsol::decoder::generate::{errors_from_rust, to_registry_json};
+use ohsol::decoder::registry::{
+    idl_program_errors, read_error_db_file, ErrorDatabase, ProgramErrors,
+};
+use ohsol::idl::parse_idl;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(about = "Generate ohsol registry entries")]
+struct Args {
+    #[command(subcommand)]
+    source: Source,
+
+    /// Merge into this errors.json (rewriting it) instead of printing
+    #[arg(long, global = true, value_name = "PATH")]
+    merge: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum Source {
+    /// Read the `errors` of one or more Anchor IDLs
+    Idl {
+        paths: Vec<PathBuf>,
+
+        /// Program ID, for a single IDL that doesn't record its address
+        #[arg(long)]
+        program_id: Option<String>,
+
+        /// Registry name (defaults to the IDL's name)
+        #[arg(long)]
+        name: Option<String>,
+    },
+
+    /// Read an error enum from Rust source
+    Rust {
+        path: PathBuf,
+
+        /// Name of the error enum
+        #[arg(long = "enum")]
+        enum_name: String,
+
+        #[arg(long)]
+        program_id: String,
+
+        /// Registry name, e.g. "spl-token-2022"
+        #[arg(long)]
+        name: String,
+    },
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let mut generated = ErrorDatabase::default();
+
+    match &args.source {
+        Source::Idl {
+            paths,
+            program_id,
+            name,
+        } => {
+            if program_id.is_some() && paths.len() > 1 {
+                bail!("--program-id only applies to a single IDL");
+            }
+            for path in paths {
+                let data =
+                    std::fs::read_to_string(path).with_context(|| path.display().to_string())?;
+                let idl = parse_idl(&data).with_context(|| path.display().to_string())?;
+                let pid = match program_id.as_deref().or(idl.program_id()) {
+                    Some(pid) => pid.to_string(),
+                    None => bail!("{}: IDL has no address; pass --program-id", path.display()),
+                };
+                let mut program = idl_program_errors(&idl);
+                if let Some(name) = name {
+                    program.name = name.clone();
+                }
+                generated.programs.insert(pid, program);
+            }
+        }
+        Source::Rust {
+            path,
+            enum_name,
+            program_id,
+            name,
+        } => {
+            let source =
+                std::fs::read_to_string(path).with_context(|| path.display().to_string())?;
+            let errors = errors_from_rust(&source, enum_name)
+                .map_err(anyhow::Error::msg)
+                .with_context(|| path.display().to_string())?;
+            generated.programs.insert(
+                program_id.clone(),
+                ProgramErrors {
+                    name: name.clone(),
+                    errors,
+                },
+            );
+        }
+    }
+
+    let count: usize = generated.programs.values().map(|p| p.errors.len()).sum();
+
+    match &args.merge {
+        Some(path) => {
+            let mut db = read_error_db_file(path)?;
+            // Generated entries describe the whole program, so replace rather
+            // than merge per code: codes the program dropped go away too.
+            for (program_id, program) in generated.programs {
+                db.programs.insert(program_id, program);
+            }
+            std::fs::write(path, to_registry_json(&db))?;
+            eprintln!("Wrote {} error(s) to {}", count, path.display());
+        }
+        None => print!("{}", to_registry_json(&generated)),
+    }
+
+    Ok(())
+}
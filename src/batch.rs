@@ -0,0 +1,260 @@
+use crate::decoder::registry::Registry;
+use crate::decoder::Decoder;
+use crate::error::ErrorReport;
+use crate::rpc;
+use crate::transaction::TransactionReport;
+use crate::{OhsolError, Result};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Concurrent requests when the caller doesn't say.
+pub const DEFAULT_CONCURRENCY: usize = 8;
+
+/// The outcome of looking up one transaction of a batch.
+#[derive(Serialize, Debug)]
+pub struct BatchEntry {
+    pub signature: String,
+    /// The decoded transaction, unless the lookup failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report: Option<TransactionReport>,
+    /// Why the lookup failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorReport>,
+}
+
+/// How many transactions share an error or a failing program.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Tally {
+    pub label: String,
+    pub count: usize,
+}
+
+/// Aggregate view of a batch, for triaging many failures at once.
+#[derive(Serialize, Debug, Default)]
+pub struct BatchSummary {
+    pub transactions: usize,
+    /// Transactions that decoded to at least one error.
+    pub failed: usize,
+    /// Transactions that couldn't be fetched.
+    pub lookup_errors: usize,
+    /// Transactions per error name (or hex code when unnamed), most common first.
+    pub errors: Vec<Tally>,
+    /// Transactions per erroring program, most common first.
+    pub programs: Vec<Tally>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct BatchReport {
+    pub results: Vec<BatchEntry>,
+    pub summary: BatchSummary,
+}
+
+/// Fetch and decode `signatures` through `decoder` on at most `concurrency`
+/// worker threads sharing one RPC client. Results keep the order of
+/// `signatures`; a failed lookup is recorded in its entry rather than failing
+/// the batch.
+pub fn fetch_transaction_reports(
+    decoder: &Decoder,
+    signatures: &[String],
+    concurrency: usize,
+) -> Result<BatchReport> {
+    let rpc_url = decoder.rpc_url().ok_or(OhsolError::NoRpcUrl)?;
+    let client = rpc::client(rpc_url);
+    let next = AtomicUsize::new(0);
+    let slots: Mutex<Vec<Option<BatchEntry>>> =
+        Mutex::new(signatures.iter().map(|_| None).collect());
+
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..concurrency.clamp(1, signatures.len().max(1)))
+            .map(|_| {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(signature) = signatures.get(index) else {
+                        break;
+                    };
+                    let result = rpc::fetch_transaction_report_with(&client, decoder, signature);
+                    slots.lock().unwrap()[index] = Some(entry(signature.clone(), result));
+                })
+            })
+            .collect();
+        // A panicking worker only loses the lookup it was on; the others carry
+        // on with the rest.
+        for worker in workers {
+            let _ = worker.join();
+        }
+    });
+
+    let results: Vec<BatchEntry> = slots
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .zip(signatures)
+        .map(|(slot, signature)| {
+            slot.unwrap_or_else(|| {
+                entry(
+                    signature.clone(),
+                    Err(OhsolError::Rpc("lookup panicked".to_string())),
+                )
+            })
+        })
+        .collect();
+
+    let summary = summarize(&results, decoder.registry());
+    Ok(BatchReport { results, summary })
+}
+
+fn entry(signature: String, result: Result<TransactionReport>) -> BatchEntry {
+    match result {
+        Ok(report) => BatchEntry {
+            signature,
+            report: Some(report),
+            error: None,
+        },
+        Err(e) => BatchEntry {
+            signature,
+            report: None,
+            error: Some(ErrorReport::from(&e)),
+        },
+    }
+}
+
+/// Count, per error and per program, the transactions it appears in. A
+/// transaction whose error propagates through several CPI levels counts once.
+/// Programs are named from `registry`.
+pub fn summarize(entries: &[BatchEntry], registry: &Registry) -> BatchSummary {
+    let mut summary = BatchSummary {
+        transactions: entries.len(),
+        ..BatchSummary::default()
+    };
+    let mut errors: HashMap<String, usize> = HashMap::new();
+    let mut programs: HashMap<String, usize> = HashMap::new();
+
+    for entry in entries {
+        let Some(report) = &entry.report else {
+            summary.lookup_errors += 1;
+            continue;
+        };
+        if report.errors.is_empty() {
+            continue;
+        }
+        summary.failed += 1;
+
+        let names = error_labels(report);
+        let program_ids: BTreeSet<&str> = report
+            .errors
+            .iter()
+            .map(|e| e.program_id.as_str())
+            .collect();
+        for name in names {
+            *errors.entry(name).or_default() += 1;
+        }
+        for program_id in program_ids {
+            let label = registry
+                .get_program_name(program_id)
+                .map(|name| format!("{} ({})", name, program_id))
+                .unwrap_or_else(|| program_id.to_string());
+            *programs.entry(label).or_default() += 1;
+        }
+    }
+
+    summary.errors = tallies(errors);
+    summary.programs = tallies(programs);
+    summary
+}
+
+/// The distinct errors of a transaction, by name or (when unnamed) hex code.
+pub(crate) fn error_labels(report: &TransactionReport) -> BTreeSet<String> {
+    report
+        .errors
+        .iter()
+        .map(|e| e.error_name.clone().unwrap_or_else(|| e.error_hex.clone()))
+        .collect()
+}
+
+pub(crate) fn tallies(counts: HashMap<String, usize>) -> Vec<Tally> {
+    let mut tallies: Vec<Tally> = counts
+        .into_iter()
+        .map(|(label, count)| Tally { label, count })
+        .collect();
+    tallies.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.label.cmp(&b.label)));
+    tallies
+}
+
+/// Signatures from a list: one per line or separated by whitespace or commas,
+/// with `#` comments. Repeats are dropped.
+pub fn parse_signature_list(text: &str) -> Vec<String> {
+    let mut seen = BTreeSet::new();
+    text.lines()
+        .map(|line| line.split('#').next().unwrap_or(""))
+        .flat_map(|line| line.split(|c: char| c.is_whitespace() || c == ','))
+        .filter(|sig| !sig.is_empty())
+        .filter(|sig| seen.insert(sig.to_string()))
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::decode_error;
+
+    fn report(signature: &str, codes: &[(u32, &str)]) -> BatchEntry {
+        BatchEntry {
+            signature: signature.to_string(),
+            report: Some(TransactionReport {
+                signature: signature.to_string(),
+                errors: codes
+                    .iter()
+                    .map(|(code, pid)| decode_error(*code, Some(pid)))
+                    .collect(),
+                ..TransactionReport::default()
+            }),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_summarize_counts_transactions() {
+        let jupiter = "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4";
+        let token = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+        let entries = vec![
+            // The same error reported at two CPI depths counts once.
+            report("a", &[(6001, jupiter), (6001, jupiter)]),
+            report("b", &[(6001, jupiter), (1, token)]),
+            report("c", &[]),
+            entry(
+                "d".to_string(),
+                Err(OhsolError::TransactionNotFound("d".to_string())),
+            ),
+        ];
+
+        let summary = summarize(&entries, &Registry::builtin());
+        assert_eq!(
+            (summary.transactions, summary.failed, summary.lookup_errors),
+            (4, 2, 1)
+        );
+        assert_eq!(
+            summary.errors,
+            vec![
+                Tally {
+                    label: "SlippageToleranceExceeded".to_string(),
+                    count: 2
+                },
+                Tally {
+                    label: "InsufficientFunds".to_string(),
+                    count: 1
+                },
+            ]
+        );
+        assert_eq!(summary.programs[0].count, 2);
+        assert!(summary.programs[0].label.contains(jupiter));
+    }
+
+    #[test]
+    fn test_parse_signature_list() {
+        let list = "# failed swaps\nsig1\nsig2, sig3  # from alerts\n\nsig1\n";
+        assert_eq!(parse_signature_list(list), ["sig1", "sig2", "sig3"]);
+    }
+}
@@ -1,4 +1,5 @@
 use clap::{Parser, Subcommand};
+use ohsol::network::Cluster;
 use std::path::PathBuf;
 
 #[derive(Parser)]
@@ -7,11 +8,35 @@ pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
 
-    #[arg(long, global = true, env = "SOLANA_RPC_URL")]
+    /// RPC URL or cluster moniker [default: $SOLANA_RPC_URL, then the
+    /// profile, then the Solana CLI config, then mainnet]
+    #[arg(long, short = 'u', global = true)]
     pub rpc_url: Option<String>,
 
+    /// Public cluster to use: mainnet, devnet, testnet or localnet
+    #[arg(long, global = true, conflicts_with = "rpc_url")]
+    pub cluster: Option<Cluster>,
+
+    /// Named profile from the config file
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+
+    /// WebSocket URL for subscriptions (derived from the RPC URL if omitted)
+    #[arg(long, global = true, env = "SOLANA_WS_URL")]
+    pub ws_url: Option<String>,
+
     #[arg(long, global = true)]
     pub json: bool,
+
+    /// Extra error database (errors.json schema or Anchor IDL), file or
+    /// directory. Repeatable; later ones take precedence
+    #[arg(long = "errors-db", global = true, value_name = "PATH")]
+    pub errors_db: Vec<PathBuf>,
+
+    /// Anchor workspace to load program IDLs from (detected from the current
+    /// directory if omitted)
+    #[arg(long, global = true, value_name = "PATH")]
+    pub workspace: Option<PathBuf>,
 }
 
 #[derive(Subcommand)]
@@ -32,8 +57,32 @@ pub enum Commands {
 
     /// Parse a transaction and explain errors
     Tx {
-        /// Transaction signature
-        signature: String,
+        /// Transaction signature. Several are analyzed as a batch
+        #[arg(required_unless_present_any = ["file", "from_file"])]
+        signatures: Vec<String>,
+
+        /// Decode a saved `getTransaction` response, `solana confirm -v
+        /// --output json` output or JSON array of logs instead (`-` for stdin)
+        #[arg(long, conflicts_with_all = ["signatures", "from_file"], value_name = "PATH")]
+        file: Option<PathBuf>,
+
+        /// Read signatures to analyze as a batch from a file, one per line
+        /// (`-` for stdin)
+        #[arg(long, value_name = "PATH")]
+        from_file: Option<PathBuf>,
+
+        /// Transactions fetched at once in a batch
+        #[arg(long, default_value_t = ohsol::batch::DEFAULT_CONCURRENCY, value_name = "N")]
+        concurrency: usize,
+
+        /// Show the full program invocation (CPI) tree (single transaction only)
+        #[arg(long, conflicts_with = "from_file")]
+        tree: bool,
+
+        /// Break down compute unit usage and the ComputeBudget settings
+        /// (single transaction only)
+        #[arg(long, conflicts_with = "from_file")]
+        compute: bool,
     },
 
     /// Fetch and cache a program's IDL
@@ -44,6 +93,66 @@ pub enum Commands {
         /// Output file path
         #[arg(short, long)]
         output: Option<PathBuf>,
+
+        /// Ignore the cache and fetch the IDL from RPC
+        #[arg(long)]
+        refresh: bool,
+    },
+
+    /// Simulate a serialized transaction and explain why it fails
+    Simulate {
+        /// Base64 or base58 transaction, or a path to a file containing one
+        transaction: String,
+
+        /// Simulate with the transaction's own blockhash instead of a fresh one
+        #[arg(long)]
+        keep_blockhash: bool,
+
+        /// Show the full program invocation (CPI) tree
+        #[arg(long)]
+        tree: bool,
+    },
+
+    /// Stream a program's transactions and decode failures live
+    Watch {
+        /// Program ID to watch
+        program_id: String,
+
+        /// Also show transactions that succeeded
+        #[arg(long)]
+        all: bool,
+
+        /// Stop after this many transactions have been shown
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+
+    /// Decode an address's recent failed transactions and chart their causes
+    Scan {
+        /// Program or account address
+        address: String,
+
+        /// Recent signatures to look at, failed or not
+        #[arg(long, default_value_t = 1000)]
+        limit: usize,
+
+        /// Start from transactions older than this signature
+        #[arg(long, value_name = "SIGNATURE")]
+        before: Option<String>,
+
+        /// Stop at this signature
+        #[arg(long, value_name = "SIGNATURE")]
+        until: Option<String>,
+
+        /// Failed transactions fetched at once
+        #[arg(long, default_value_t = ohsol::batch::DEFAULT_CONCURRENCY, value_name = "N")]
+        concurrency: usize,
+    },
+
+    /// Manage the on-disk IDL cache
+    Cache {
+        #[command(subcommand)]
+        action: CacheCommand,
     },
 
     /// List known errors for a program
@@ -51,4 +160,45 @@ pub enum Commands {
         /// Program ID or name (e.g., "spl-token", "jupiter")
         program: String,
     },
+
+    /// Find errors by name or message across all known programs
+    Search {
+        /// Words to look for (e.g., "slippage", "insufficient funds")
+        #[arg(required = true)]
+        query: Vec<String>,
+    },
+}
+
+impl Commands {
+    /// Whether the command can't do its job without an RPC endpoint. The others
+    /// still run, offline, when the config or endpoint is broken.
+    pub fn needs_rpc(&self) -> bool {
+        !matches!(
+            self,
+            Commands::Decode { .. }
+                | Commands::List { .. }
+                | Commands::Search { .. }
+                | Commands::Cache {
+                    action: CacheCommand::List | CacheCommand::Clear { .. }
+                }
+        )
+    }
+}
+
+#[derive(Subcommand)]
+pub enum CacheCommand {
+    /// List cached IDLs
+    List,
+
+    /// Remove cached IDLs
+    Clear {
+        /// Only remove this program (all programs if omitted)
+        program_id: Option<String>,
+    },
+
+    /// Re-fetch cached IDLs for the current network
+    Refresh {
+        /// Only refresh this program (all cached programs if omitted)
+        program_id: Option<String>,
+    },
 }
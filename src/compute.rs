@@ -0,0 +1,357 @@
+use crate::decoder::registry::Registry;
+use crate::invocation::{Invocation, Outcome};
+use crate::DecodedError;
+use serde::Serialize;
+use solana_sdk::message::VersionedMessage;
+use std::collections::HashMap;
+
+pub const COMPUTE_BUDGET_PROGRAM: &str = "ComputeBudget111111111111111111111111111111";
+
+/// Units each instruction gets when the transaction doesn't set a limit.
+pub const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u64 = 200_000;
+
+/// Units each builtin-program instruction gets instead.
+pub const MAX_BUILTIN_ALLOCATION_COMPUTE_UNIT_LIMIT: u64 = 3_000;
+
+/// Programs the runtime runs natively, which get the builtin allocation.
+pub const BUILTIN_PROGRAMS: &[&str] = &[
+    "11111111111111111111111111111111",
+    "Vote111111111111111111111111111111111111111",
+    "Stake11111111111111111111111111111111111111",
+    "Config1111111111111111111111111111111111111",
+    "AddressLookupTab1e1111111111111111111111111",
+    COMPUTE_BUDGET_PROGRAM,
+    "BPFLoader1111111111111111111111111111111111",
+    "BPFLoader2111111111111111111111111111111111",
+    "BPFLoaderUpgradeab1e11111111111111111111111",
+    "LoaderV411111111111111111111111111111111111",
+    "Ed25519SigVerify111111111111111111111111111",
+    "KeccakSecp256k11111111111111111111111111111",
+    "Secp256r1SigVerify1111111111111111111111111",
+    "ZkE1Gama1Proof11111111111111111111111111111",
+];
+
+/// The most units a transaction can request.
+pub const MAX_COMPUTE_UNIT_LIMIT: u64 = 1_400_000;
+
+/// What a transaction asked for with ComputeBudget instructions.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct ComputeBudget {
+    /// `SetComputeUnitLimit`.
+    pub unit_limit: Option<u32>,
+    /// `SetComputeUnitPrice`, in micro-lamports per unit.
+    pub unit_price: Option<u64>,
+    /// `RequestHeapFrame`, in bytes.
+    pub heap_frame_bytes: Option<u32>,
+    /// `SetLoadedAccountsDataSizeLimit`, in bytes.
+    pub loaded_accounts_data_size_limit: Option<u32>,
+}
+
+/// Where a transaction's compute went, and why it ran out if it did.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct ComputeReport {
+    pub budget: ComputeBudget,
+    /// The transaction's unit limit: the requested one, else the runtime
+    /// default. Unknown without the message.
+    pub limit: Option<u64>,
+    /// Units consumed, as reported by the node or summed from the logs.
+    pub used: Option<u64>,
+    /// Priority fee paid for the requested limit, in lamports.
+    pub priority_fee_lamports: Option<u64>,
+    /// The failure was running out of compute units.
+    pub exceeded: bool,
+    pub diagnosis: Option<String>,
+}
+
+/// Read the ComputeBudget instructions of `message`. Later instructions of
+/// the same kind win, though the runtime rejects duplicates.
+pub fn compute_budget(message: &VersionedMessage) -> ComputeBudget {
+    let keys = message.static_account_keys();
+    let mut budget = ComputeBudget::default();
+
+    for ix in message.instructions() {
+        let is_compute_budget = keys
+            .get(ix.program_id_index as usize)
+            .is_some_and(|key| key.to_string() == COMPUTE_BUDGET_PROGRAM);
+        if !is_compute_budget {
+            continue;
+        }
+        let u32_arg = || {
+            ix.data
+                .get(1..5)
+                .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+        };
+        match ix.data.first() {
+            Some(1) => budget.heap_frame_bytes = u32_arg(),
+            Some(2) => budget.unit_limit = u32_arg(),
+            Some(3) => {
+                budget.unit_price = ix
+                    .data
+                    .get(1..9)
+                    .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+            }
+            Some(4) => budget.loaded_accounts_data_size_limit = u32_arg(),
+            _ => {}
+        }
+    }
+
+    budget
+}
+
+/// Analyze compute usage from the invocation tree, the message's budget
+/// instructions (when available) and the decoded errors. `units_consumed` is
+/// the node's total, when it reports one; `registry` names programs in the
+/// diagnosis.
+pub fn analyze_compute(
+    message: Option<&VersionedMessage>,
+    invocations: &[Invocation],
+    errors: &[DecodedError],
+    units_consumed: Option<u64>,
+    registry: &Registry,
+) -> ComputeReport {
+    let budget = message.map(compute_budget).unwrap_or_default();
+    let limit = message.map(|message| match budget.unit_limit {
+        Some(limit) => u64::from(limit).min(MAX_COMPUTE_UNIT_LIMIT),
+        None => default_limit(message),
+    });
+    // Roots without a consumed line (e.g. builtins, or truncated logs) add
+    // nothing rather than hiding what the others report.
+    let used = units_consumed.or_else(|| {
+        invocations
+            .iter()
+            .filter_map(|root| root.compute_used)
+            .reduce(|total, used| total + used)
+    });
+    let priority_fee_lamports = budget
+        .unit_price
+        .zip(limit)
+        .map(|(price, limit)| (u128::from(price) * u128::from(limit)).div_ceil(1_000_000) as u64);
+
+    let exceeded = errors
+        .iter()
+        .any(|e| e.error_name.as_deref() == Some("ComputationalBudgetExceeded"))
+        || invocations.iter().filter_map(Invocation::failing_leaf).any(
+            |leaf| matches!(&leaf.outcome, Outcome::Failed { error } if is_out_of_compute(error)),
+        );
+
+    let mut report = ComputeReport {
+        budget,
+        limit,
+        used,
+        priority_fee_lamports,
+        exceeded,
+        diagnosis: None,
+    };
+    if exceeded {
+        report.diagnosis = Some(diagnose(&report, invocations, registry));
+    }
+    report
+}
+
+/// Without `SetComputeUnitLimit`, builtin-program instructions (the budget
+/// ones included) get the builtin allocation and every other instruction
+/// the default, up to the maximum.
+fn default_limit(message: &VersionedMessage) -> u64 {
+    let keys = message.static_account_keys();
+    let limit: u64 = message
+        .instructions()
+        .iter()
+        .map(|ix| {
+            let builtin = keys
+                .get(ix.program_id_index as usize)
+                .is_some_and(|key| BUILTIN_PROGRAMS.contains(&key.to_string().as_str()));
+            if builtin {
+                MAX_BUILTIN_ALLOCATION_COMPUTE_UNIT_LIMIT
+            } else {
+                DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT
+            }
+        })
+        .sum();
+    limit.min(MAX_COMPUTE_UNIT_LIMIT)
+}
+
+fn is_out_of_compute(error: &str) -> bool {
+    error.contains("exceeded CUs meter") || error.contains("Computational budget exceeded")
+}
+
+fn diagnose(report: &ComputeReport, invocations: &[Invocation], registry: &Registry) -> String {
+    let mut text = String::from("The transaction ran out of compute units");
+    match (report.used, report.limit) {
+        (Some(used), Some(limit)) => {
+            let source = if report.budget.unit_limit.is_some() {
+                "set by SetComputeUnitLimit"
+            } else {
+                "the default"
+            };
+            text.push_str(&format!(
+                ": used {} of its {} CU limit ({})",
+                used, limit, source
+            ));
+        }
+        (Some(used), None) => text.push_str(&format!(" after using {} CU", used)),
+        _ => {}
+    }
+    text.push('.');
+
+    if let Some((program_id, units)) = heaviest_program(invocations) {
+        let program = match registry.get_program_name(&program_id) {
+            Some(name) => format!("{} ({})", name, program_id),
+            None => program_id,
+        };
+        text.push_str(&format!(
+            " {} used the most itself ({} CU, excluding its CPIs).",
+            program, units
+        ));
+    }
+
+    match report.limit {
+        Some(limit) if limit >= MAX_COMPUTE_UNIT_LIMIT => text.push_str(
+            " The limit is already at the 1,400,000 CU maximum, so the work has to be split across transactions.",
+        ),
+        _ if report.budget.unit_limit.is_some() => {
+            text.push_str(" Raise the SetComputeUnitLimit value, with headroom over what simulation reports.")
+        }
+        _ => text.push_str(
+            " Add a SetComputeUnitLimit instruction sized from a simulation, with headroom, or reduce the work per transaction.",
+        ),
+    }
+    text
+}
+
+/// The program that consumed the most units itself, over the whole tree.
+fn heaviest_program(invocations: &[Invocation]) -> Option<(String, u64)> {
+    fn walk(invocation: &Invocation, totals: &mut HashMap<String, u64>) {
+        if let Some(units) = invocation.self_compute_used() {
+            *totals.entry(invocation.program_id.clone()).or_default() += units;
+        }
+        for child in &invocation.children {
+            walk(child, totals);
+        }
+    }
+
+    let mut totals = HashMap::new();
+    for root in invocations {
+        walk(root, &mut totals);
+    }
+    totals
+        .into_iter()
+        .filter(|(_, units)| *units > 0)
+        .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::Decoder;
+    use crate::invocation::build_invocation_tree;
+    use crate::transaction::decode_log_errors;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::instruction::Instruction;
+    use solana_sdk::message::Message;
+    use solana_sdk::pubkey::Pubkey;
+    use std::str::FromStr;
+
+    fn budget_instruction(data: Vec<u8>) -> Instruction {
+        Instruction::new_with_bytes(
+            Pubkey::from_str(COMPUTE_BUDGET_PROGRAM).unwrap(),
+            &data,
+            vec![],
+        )
+    }
+
+    #[test]
+    fn test_compute_budget_and_exhaustion() {
+        let program = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let mut price = vec![3];
+        price.extend(50_000u64.to_le_bytes());
+        let mut limit = vec![2];
+        limit.extend(150_000u32.to_le_bytes());
+        let message = VersionedMessage::Legacy(Message::new_with_blockhash(
+            &[
+                budget_instruction(limit),
+                budget_instruction(price.clone()),
+                Instruction::new_with_bytes(program, &[], vec![]),
+            ],
+            Some(&payer),
+            &Hash::default(),
+        ));
+
+        let token = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+        let logs: Vec<String> = [
+            format!("Program {} invoke [1]", program),
+            format!("Program {} invoke [2]", token),
+            format!("Program {} consumed 4000 of 140000 compute units", token),
+            format!("Program {} success", token),
+            format!(
+                "Program {} consumed 149700 of 149700 compute units",
+                program
+            ),
+            format!(
+                "Program {} failed: exceeded CUs meter at BPF instruction",
+                program
+            ),
+        ]
+        .to_vec();
+        let invocations = build_invocation_tree(&logs);
+        let decoder = Decoder::new();
+        let errors = decode_log_errors(&logs, &decoder);
+
+        let report = analyze_compute(
+            Some(&message),
+            &invocations,
+            &errors,
+            None,
+            decoder.registry(),
+        );
+        assert_eq!(report.budget.unit_limit, Some(150_000));
+        assert_eq!(report.budget.unit_price, Some(50_000));
+        assert_eq!((report.limit, report.used), (Some(150_000), Some(149_700)));
+        assert_eq!(report.priority_fee_lamports, Some(7500));
+        assert!(report.exceeded);
+        let diagnosis = report.diagnosis.unwrap();
+        assert!(
+            diagnosis.contains("used 149700 of its 150000 CU limit (set by SetComputeUnitLimit)")
+        );
+        assert!(diagnosis.contains(&format!("{} used the most itself (145700 CU", program)));
+
+        // Without budget instructions each instruction gets the default.
+        let plain = VersionedMessage::Legacy(Message::new(
+            &vec![Instruction::new_with_bytes(program, &[], vec![]); 2],
+            Some(&payer),
+        ));
+        let report = analyze_compute(Some(&plain), &[], &[], Some(1000), decoder.registry());
+        assert_eq!(report.limit, Some(400_000));
+        assert!(!report.exceeded && report.diagnosis.is_none());
+
+        // Usage sums over the roots that report it.
+        let logs: Vec<String> = [
+            "Program 11111111111111111111111111111111 invoke [1]".to_string(),
+            "Program 11111111111111111111111111111111 success".to_string(),
+            format!("Program {} invoke [1]", program),
+            format!("Program {} consumed 1200 of 200000 compute units", program),
+            format!("Program {} success", program),
+        ]
+        .to_vec();
+        let report = analyze_compute(
+            None,
+            &build_invocation_tree(&logs),
+            &[],
+            None,
+            decoder.registry(),
+        );
+        assert_eq!(report.used, Some(1200));
+
+        // Builtin programs get far less.
+        let transfer = VersionedMessage::Legacy(Message::new(
+            &[
+                solana_sdk::system_instruction::transfer(&payer, &program, 1),
+                budget_instruction(price),
+                Instruction::new_with_bytes(program, &[], vec![]),
+            ],
+            Some(&payer),
+        ));
+        let report = analyze_compute(Some(&transfer), &[], &[], None, decoder.registry());
+        assert_eq!(report.limit, Some(206_000));
+    }
+}
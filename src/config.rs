@@ -0,0 +1,120 @@
+use crate::{OhsolError, Result};
+use serde::Deserialize;
+use solana_sdk::commitment_config::CommitmentLevel;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Settings read from `config.toml`.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Error database files or directories, merged over the built-in registry.
+    pub errors_db: Vec<PathBuf>,
+    /// Profile used when `--profile` isn't given.
+    pub default_profile: Option<String>,
+    /// Named environments, selected with `--profile`.
+    pub profiles: HashMap<String, Profile>,
+}
+
+/// A named environment: where to send requests and what to load for it.
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    /// RPC URL or cluster moniker (`mainnet`, `devnet`, `testnet`, `localnet`).
+    pub rpc_url: Option<String>,
+    pub ws_url: Option<String>,
+    /// HTTP headers sent with every RPC request.
+    pub headers: BTreeMap<String, String>,
+    pub commitment: Option<CommitmentLevel>,
+    /// Error databases loaded after the top-level `errors_db`.
+    pub errors_db: Vec<PathBuf>,
+}
+
+/// `$OHSOL_CONFIG`, else `config.toml` in `$XDG_CONFIG_HOME/ohsol` or
+/// `~/.config/ohsol`.
+pub fn config_path() -> Option<PathBuf> {
+    if let Ok(path) = std::env::var("OHSOL_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|base| base.join("ohsol").join("config.toml"))
+}
+
+/// Load the config file. A missing file is an empty config.
+pub fn load() -> Result<Config> {
+    match config_path() {
+        Some(path) if path.is_file() => load_from(&path),
+        _ => Ok(Config::default()),
+    }
+}
+
+pub fn load_from(path: &Path) -> Result<Config> {
+    let data = std::fs::read_to_string(path).map_err(|e| OhsolError::invalid_file(path, e))?;
+    let mut config: Config = toml::from_str(&data)
+        .map_err(|e| OhsolError::invalid_file(path, format!("Invalid config: {}", e)))?;
+
+    // Relative paths are relative to the config file, not the working directory.
+    let base = path.parent().unwrap_or(Path::new("."));
+    config.errors_db = config
+        .errors_db
+        .into_iter()
+        .map(|p| resolve_path(base, &p))
+        .collect();
+    for profile in config.profiles.values_mut() {
+        profile.errors_db = profile
+            .errors_db
+            .iter()
+            .map(|p| resolve_path(base, p))
+            .collect();
+    }
+
+    Ok(config)
+}
+
+fn resolve_path(base: &Path, path: &Path) -> PathBuf {
+    if let Ok(rest) = path.strip_prefix("~") {
+        if let Some(home) = std::env::var_os("HOME") {
+            return PathBuf::from(home).join(rest);
+        }
+    }
+    base.join(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_resolves_paths() {
+        let dir = std::env::temp_dir().join(format!("ohsol-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        std::fs::write(
+            &path,
+            "errors_db = [\"errors\", \"/opt/ohsol/extra.json\"]\n\
+             default_profile = \"dev\"\n\
+             [profiles.dev]\n\
+             rpc_url = \"devnet\"\n\
+             commitment = \"finalized\"\n\
+             errors_db = [\"dev-errors.json\"]\n\
+             headers = { x-api-key = \"secret\" }\n",
+        )
+        .unwrap();
+
+        let config = load_from(&path).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(
+            config.errors_db,
+            vec![dir.join("errors"), PathBuf::from("/opt/ohsol/extra.json")]
+        );
+        let dev = &config.profiles["dev"];
+        assert_eq!(dev.errors_db, vec![dir.join("dev-errors.json")]);
+        assert_eq!(dev.commitment, Some(CommitmentLevel::Finalized));
+        assert_eq!(dev.headers["x-api-key"], "secret");
+    }
+}
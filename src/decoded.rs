@@ -0,0 +1,123 @@
+use serde::Serialize;
+
+/// One decoded program or runtime error, with whatever context could be
+/// recovered from the logs.
+#[derive(Serialize, Debug, Clone)]
+pub struct DecodedError {
+    pub program_id: String,
+    pub error_code: u32,
+    pub error_hex: String,
+    pub error_name: Option<String>,
+    pub error_message: Option<String>,
+    pub source_file: Option<String>,
+    pub source_line: Option<u32>,
+    pub cpi_depth: Option<u8>,
+    pub instruction_index: Option<u8>,
+    pub explanation: Option<String>,
+    pub likely_causes: Vec<String>,
+    pub account_name: Option<String>,
+    pub left_value: Option<String>,
+    pub right_value: Option<String>,
+    /// Other ways to read the code when it couldn't be pinned to one program,
+    /// best first.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub alternatives: Vec<Candidate>,
+}
+
+/// How sure the decoder is that a candidate is what the code means.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+/// One interpretation of an error code, with why it was suggested.
+#[derive(Serialize, Debug, Clone)]
+pub struct Candidate {
+    #[serde(flatten)]
+    pub error: DecodedError,
+    pub confidence: Confidence,
+    pub reason: String,
+}
+
+impl Candidate {
+    pub fn new(error: DecodedError, confidence: Confidence, reason: impl Into<String>) -> Self {
+        Self {
+            error,
+            confidence,
+            reason: reason.into(),
+        }
+    }
+}
+
+impl DecodedError {
+    pub fn new(code: u32) -> Self {
+        Self {
+            program_id: String::new(),
+            error_code: code,
+            error_hex: format!("0x{:x}", code),
+            error_name: None,
+            error_message: None,
+            source_file: None,
+            source_line: None,
+            cpi_depth: None,
+            instruction_index: None,
+            explanation: None,
+            likely_causes: Vec::new(),
+            account_name: None,
+            left_value: None,
+            right_value: None,
+            alternatives: Vec::new(),
+        }
+    }
+
+    pub fn with_program(mut self, program_id: String) -> Self {
+        self.program_id = program_id;
+        self
+    }
+
+    pub fn with_name(mut self, name: String) -> Self {
+        self.error_name = Some(name);
+        self
+    }
+
+    pub fn with_message(mut self, message: String) -> Self {
+        self.error_message = Some(message);
+        self
+    }
+
+    pub fn with_source(mut self, file: String, line: u32) -> Self {
+        self.source_file = Some(file);
+        self.source_line = Some(line);
+        self
+    }
+
+    pub fn with_cpi_depth(mut self, depth: u8) -> Self {
+        self.cpi_depth = Some(depth);
+        self
+    }
+
+    pub fn with_explanation(mut self, explanation: String, likely_causes: Vec<String>) -> Self {
+        self.explanation = Some(explanation);
+        self.likely_causes = likely_causes;
+        self
+    }
+
+    pub fn with_account(mut self, account: String) -> Self {
+        self.account_name = Some(account);
+        self
+    }
+
+    pub fn with_compared_values(mut self, left: Option<String>, right: Option<String>) -> Self {
+        self.left_value = left;
+        self.right_value = right;
+        self
+    }
+
+    pub fn with_instruction_index(mut self, index: u8) -> Self {
+        self.instruction_index = Some(index);
+        self
+    }
+}
@@ -1,4 +1,4 @@
-use crate::output::DecodedError;
+use crate::{DecodedError, OhsolError, Result};
 
 pub const ANCHOR_ERROR_OFFSET: u32 = 6000;
 
@@ -13,16 +13,17 @@ pub fn decode_anchor_custom(code: u32) -> DecodedError {
         .with_message(format!("Custom program error at variant index {}", variant_index))
 }
 
-pub fn parse_error_code(input: &str) -> anyhow::Result<u32> {
+pub fn parse_error_code(input: &str) -> Result<u32> {
     let input = input.trim();
+    let invalid = |e: std::num::ParseIntError| OhsolError::InvalidErrorCode(format!("{}: {}", input, e));
 
     // Try hex first
     if let Some(hex) = input.strip_prefix("0x") {
-        return Ok(u32::from_str_radix(hex, 16)?);
+        return u32::from_str_radix(hex, 16).map_err(invalid);
     }
 
     // Try decimal
-    Ok(input.parse()?)
+    input.parse().map_err(invalid)
 }
 
 #[cfg(test)]
@@ -0,0 +1,376 @@
+//! Build built-in registry entries (`data/errors.json`) from the sources
+//! programs publish: Anchor IDLs and the Rust enums native programs define
+//! their errors with. `examples/gen_registry.rs` wraps this in a CLI.
+
+use super::registry::{ErrorDatabase, ErrorEntry, ProgramErrors};
+use regex::Regex;
+use std::collections::HashMap;
+
+/// Read the errors of `enum_name` from Rust source.
+///
+/// Codes count up from 0, or from 6000 (or the given `offset`) for Anchor
+/// `#[error_code]` enums, and explicit discriminants reset the count. Each
+/// message comes from the variant's `#[error("...")]` or `#[msg("...")]`
+/// attribute, else a `Display` arm returning a string literal, else its doc
+/// comment.
+pub fn errors_from_rust(source: &str, enum_name: &str) -> Result<Vec<ErrorEntry>, String> {
+    let enum_re = Regex::new(&format!(r"\benum\s+{}\b", regex::escape(enum_name))).unwrap();
+    let start = enum_re
+        .find(source)
+        .ok_or_else(|| format!("enum {} not found", enum_name))?;
+
+    let mut code = anchor_offset(&source[..start.start()]).unwrap_or(0);
+    let open = source[start.end()..]
+        .find('{')
+        .map(|i| start.end() + i + 1)
+        .ok_or_else(|| format!("enum {} has no body", enum_name))?;
+    let close = matching_brace(&source[open..])
+        .ok_or_else(|| format!("enum {} is not closed", enum_name))?;
+    let body = &source[open..open + close];
+
+    let display = display_messages(source, enum_name);
+    let mut errors = Vec::new();
+    let mut docs: Vec<String> = Vec::new();
+    let mut message: Option<String> = None;
+
+    for item in logical_lines(body) {
+        let item = item.trim();
+        if let Some(doc) = item.strip_prefix("///") {
+            docs.push(doc.trim().to_string());
+        } else if item.starts_with("//") || item.is_empty() {
+            continue;
+        } else if let Some(attr) = item.strip_prefix("#[") {
+            if attr.starts_with("error(") || attr.starts_with("msg(") {
+                message = first_string_literal(attr).or(message);
+            }
+        } else {
+            for variant in split_top_level(item, ',') {
+                let Some(name) = leading_ident(variant) else {
+                    continue;
+                };
+                if let Some((_, value)) = variant.split_once('=') {
+                    code = parse_code(value.trim()).ok_or_else(|| {
+                        format!("{}: unsupported discriminant {}", name, value.trim())
+                    })?;
+                }
+
+                let msg = message
+                    .take()
+                    .or_else(|| display.get(name).cloned())
+                    .unwrap_or_else(|| docs.join(" ").trim_end_matches('.').to_string());
+                errors.push(ErrorEntry {
+                    code,
+                    name: name.to_string(),
+                    msg,
+                });
+                docs.clear();
+                code += 1;
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        return Err(format!("enum {} has no variants", enum_name));
+    }
+    Ok(errors)
+}
+
+/// Render a database in the layout of `data/errors.json`: programs sorted by
+/// name, one error per line.
+pub fn to_registry_json(db: &ErrorDatabase) -> String {
+    let mut programs: Vec<(&String, &ProgramErrors)> = db.programs.iter().collect();
+    programs.sort_by(|a, b| (&a.1.name, a.0).cmp(&(&b.1.name, b.0)));
+
+    let mut out = String::from("{\n  \"programs\": {\n");
+    for (i, (program_id, program)) in programs.iter().enumerate() {
+        out.push_str(&format!("    {}: {{\n", json_string(program_id)));
+        out.push_str(&format!(
+            "      \"name\": {},\n",
+            json_string(&program.name)
+        ));
+        out.push_str(&format!(
+            "      \"errors\": [{}]\n",
+            entry_lines(&program.errors, 8)
+        ));
+        out.push_str(if i + 1 < programs.len() {
+            "    },\n"
+        } else {
+            "    }\n"
+        });
+    }
+    out.push_str("  },\n");
+    out.push_str(&format!(
+        "  \"anchor_errors\": [{}]\n}}\n",
+        entry_lines(&db.anchor_errors, 4)
+    ));
+    out
+}
+
+fn entry_lines(errors: &[ErrorEntry], indent: usize) -> String {
+    if errors.is_empty() {
+        return String::new();
+    }
+    let pad = " ".repeat(indent);
+    let lines: Vec<String> = errors
+        .iter()
+        .map(|e| {
+            format!(
+                "{}{{ \"code\": {}, \"name\": {}, \"msg\": {} }}",
+                pad,
+                e.code,
+                json_string(&e.name),
+                json_string(&e.msg)
+            )
+        })
+        .collect();
+    format!("\n{}\n{}", lines.join(",\n"), " ".repeat(indent - 2))
+}
+
+fn json_string(s: &str) -> String {
+    serde_json::to_string(s).unwrap()
+}
+
+/// The code an Anchor `#[error_code]` enum starts at, if the attributes right
+/// before the enum make it one.
+fn anchor_offset(before_enum: &str) -> Option<u32> {
+    let attrs = &before_enum[before_enum.rfind(['}', ';']).map_or(0, |i| i + 1)..];
+    let attr = attrs.find("#[error_code").map(|i| &attrs[i..])?;
+    let offset = Regex::new(r"^#\[error_code\s*\(\s*offset\s*=\s*([0-9a-fA-Fx_]+)").unwrap();
+    Some(
+        offset
+            .captures(attr)
+            .and_then(|c| parse_code(&c[1]))
+            .unwrap_or(super::anchor::ANCHOR_ERROR_OFFSET),
+    )
+}
+
+/// Messages from `Display` match arms like `Self::Variant => "message"`.
+fn display_messages(source: &str, enum_name: &str) -> HashMap<String, String> {
+    let arm = Regex::new(&format!(
+        r#"(?:Self|{})::(\w+)\s*(?:\{{[^}}]*\}}|\([^)]*\))?\s*=>\s*\{{?\s*"((?:[^"\\]|\\.)*)""#,
+        regex::escape(enum_name)
+    ))
+    .unwrap();
+    arm.captures_iter(source)
+        .map(|c| (c[1].to_string(), unescape(&c[2])))
+        .collect()
+}
+
+/// Index of the `}` closing a block whose `{` was just consumed.
+fn matching_brace(text: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut scanner = Scanner::default();
+    for (i, c) in text.char_indices() {
+        if !scanner.code(c) {
+            continue;
+        }
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(i),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Split `body` into lines, joining lines while brackets are open so that
+/// multi-line attributes and struct variants stay in one piece.
+fn logical_lines(body: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0i32;
+
+    for line in body.lines() {
+        if depth == 0 && line.trim_start().starts_with("//") {
+            lines.push(line.to_string());
+            continue;
+        }
+        let mut scanner = Scanner::default();
+        for c in line.chars() {
+            if scanner.code(c) {
+                match c {
+                    '(' | '[' | '{' => depth += 1,
+                    ')' | ']' | '}' => depth -= 1,
+                    _ => {}
+                }
+            }
+        }
+        current.push_str(line.trim());
+        current.push(' ');
+        if depth <= 0 {
+            depth = 0;
+            lines.push(std::mem::take(&mut current));
+        }
+    }
+    lines.push(current);
+    lines
+}
+
+/// Split at `sep` outside brackets and strings.
+fn split_top_level(text: &str, sep: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0;
+    let mut scanner = Scanner::default();
+
+    for (i, c) in text.char_indices() {
+        if !scanner.code(c) {
+            continue;
+        }
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            c if c == sep && depth == 0 => {
+                parts.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+/// Tracks string literals so brackets inside messages are ignored.
+#[derive(Default)]
+struct Scanner {
+    in_string: bool,
+    escaped: bool,
+}
+
+impl Scanner {
+    /// Whether `c` is code rather than part of a string literal.
+    fn code(&mut self, c: char) -> bool {
+        if self.in_string {
+            match c {
+                _ if self.escaped => self.escaped = false,
+                '\\' => self.escaped = true,
+                '"' => self.in_string = false,
+                _ => {}
+            }
+            return false;
+        }
+        if c == '"' {
+            self.in_string = true;
+            return false;
+        }
+        true
+    }
+}
+
+fn leading_ident(text: &str) -> Option<&str> {
+    let text = text.trim();
+    let end = text
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(text.len());
+    let ident = &text[..end];
+    ident
+        .starts_with(|c: char| c.is_alphabetic())
+        .then_some(ident)
+}
+
+fn first_string_literal(text: &str) -> Option<String> {
+    let re = Regex::new(r#""((?:[^"\\]|\\.)*)""#).unwrap();
+    re.captures(text).map(|c| unescape(&c[1]))
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("\\\"", "\"").replace("\\\\", "\\")
+}
+
+fn parse_code(text: &str) -> Option<u32> {
+    let text = text.replace('_', "");
+    match text.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_errors_from_rust_enums() {
+        let native = r#"
+            pub enum PoolError {
+                // 0
+                /// Pool is full.
+                PoolFull,
+                #[error(
+                    "Amount {0} exceeds the {1} limit"
+                )]
+                TooLarge(u64, u64),
+                Paused { since: u64 } = 10,
+                Closed,
+            }
+
+            impl fmt::Display for PoolError {
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str(match self {
+                        Self::Closed => {
+                            "pool is closed"
+                        }
+                        _ => "other",
+                    })
+                }
+            }
+        "#;
+        let errors = errors_from_rust(native, "PoolError").unwrap();
+        let summary: Vec<(u32, &str, &str)> = errors
+            .iter()
+            .map(|e| (e.code, e.name.as_str(), e.msg.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (0, "PoolFull", "Pool is full"),
+                (1, "TooLarge", "Amount {0} exceeds the {1} limit"),
+                (10, "Paused", ""),
+                (11, "Closed", "pool is closed"),
+            ]
+        );
+
+        let anchor = r#"
+            #[error_code]
+            pub enum ErrorCode {
+                #[msg("Slippage exceeded")]
+                SlippageExceeded,
+                Stale,
+            }
+        "#;
+        let errors = errors_from_rust(anchor, "ErrorCode").unwrap();
+        assert_eq!(
+            (errors[0].code, errors[0].msg.as_str()),
+            (6000, "Slippage exceeded")
+        );
+        assert_eq!(errors[1].code, 6001);
+
+        assert!(errors_from_rust(anchor, "Missing").is_err());
+    }
+
+    #[test]
+    fn test_registry_json_round_trips() {
+        let mut db = ErrorDatabase::default();
+        db.programs.insert(
+            "Prog1111111111111111111111111111111111111111".to_string(),
+            ProgramErrors {
+                name: "pool".to_string(),
+                errors: vec![ErrorEntry {
+                    code: 0,
+                    name: "PoolFull".to_string(),
+                    msg: "Pool \"main\" is full".to_string(),
+                }],
+            },
+        );
+
+        let json = to_registry_json(&db);
+        let parsed: ErrorDatabase = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            parsed.programs["Prog1111111111111111111111111111111111111111"].errors[0].msg,
+            "Pool \"main\" is full"
+        );
+        assert!(json.contains("        { \"code\": 0, \"name\": \"PoolFull\""));
+    }
+}
@@ -1,46 +1,387 @@
 pub mod anchor;
+pub mod generate;
 pub mod registry;
+pub mod runtime;
 
-use crate::output::DecodedError;
+use crate::decoded::{Candidate, Confidence};
+use crate::events::{self, DecodedEvent};
+use crate::idl::cache::IdlCache;
+use crate::idl::Idl;
+use crate::instruction::{self, DecodedInstruction};
+use crate::invocation::{build_invocation_tree, Invocation};
+use crate::search::{self, ErrorMatch};
+use crate::transaction;
+use crate::workspace::{self, WorkspaceProgram};
+use crate::{DecodedError, OhsolError, Result};
+use solana_sdk::message::v0::LoadedAddresses;
+use solana_sdk::message::VersionedMessage;
 use solana_sdk::pubkey::Pubkey;
+use std::path::Path;
 use std::str::FromStr;
+use std::sync::Arc;
 
-pub fn decode_error(code: u32, program_id: Option<&str>) -> DecodedError {
-    // 1. Check built-in Anchor errors (< 6000)
-    if code < 6000 {
-        if let Some(anchor_err) = registry::lookup_anchor_error(code) {
-            return anchor_err;
+#[cfg(feature = "rpc")]
+use crate::{idl, rpc, transaction::TransactionReport};
+
+use registry::Registry;
+
+/// Decodes errors using the built-in registry plus any error sources loaded
+/// into it, optionally fetching unknown programs' IDLs over RPC.
+///
+/// Each decoder from [`Decoder::new`] has its own registry, so sources loaded
+/// into one don't affect another; clones share it. [`Decoder::shared`] uses
+/// the process-wide registry behind this module's free functions.
+#[derive(Debug, Clone, Default)]
+pub struct Decoder {
+    registry: Arc<Registry>,
+    rpc_url: Option<String>,
+}
+
+impl Decoder {
+    /// An offline decoder that never touches the network.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A decoder over the process-wide registry, which the free functions in
+    /// this module and in [`registry`] read and write.
+    pub fn shared() -> Self {
+        Self {
+            registry: registry::global(),
+            rpc_url: None,
         }
     }
 
-    // 2. Check program-specific database
-    if let Some(pid) = program_id {
-        if let Some(known_err) = registry::lookup_program_error(pid, code) {
-            return known_err;
+    /// The shared decoder, fetching through `rpc_url` if given.
+    fn shared_with(rpc_url: Option<&str>) -> Self {
+        Self {
+            registry: registry::global(),
+            rpc_url: rpc_url.map(str::to_string),
         }
     }
 
-    // 3. For custom Anchor errors (>= 6000), provide generic info
-    if anchor::is_anchor_custom_error(code) {
-        let mut error = anchor::decode_anchor_custom(code);
-        if let Some(pid) = program_id {
-            error = error.with_program(pid.to_string());
+    /// Fetch (and cache) the on-chain IDL of programs missing from the
+    /// registry, and look up transactions, through `rpc_url`.
+    #[cfg(feature = "rpc")]
+    pub fn with_rpc_url(mut self, rpc_url: impl Into<String>) -> Self {
+        self.rpc_url = Some(rpc_url.into());
+        self
+    }
+
+    pub fn rpc_url(&self) -> Option<&str> {
+        self.rpc_url.as_deref()
+    }
+
+    /// The error definitions and IDLs this decoder knows.
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    /// Merge an error database file or directory into the registry. See
+    /// [`Registry::load_error_db`].
+    pub fn load_errors_db(&self, path: impl AsRef<Path>) -> Result<usize> {
+        self.registry.load_error_db(path.as_ref())
+    }
+
+    /// Register the errors declared in `idl` for `program_id`.
+    pub fn register_idl(&self, program_id: &str, idl: &Idl) {
+        self.registry.register_idl_errors(program_id, idl);
+        self.registry.store_idl(program_id, idl.clone());
+    }
+
+    /// Register every program built in the Anchor workspace at `root`.
+    pub fn load_workspace(&self, root: impl AsRef<Path>) -> Result<Vec<WorkspaceProgram>> {
+        workspace::register_workspace(root.as_ref(), self.rpc_url(), &self.registry)
+    }
+
+    /// Decode an error code, optionally in the context of the program that
+    /// returned it. With an RPC URL, when the registry doesn't know the code
+    /// for the program and it is a custom program error, the program's
+    /// on-chain Anchor IDL is fetched and its `errors` list used.
+    pub fn decode(&self, code: u32, program_id: Option<&str>) -> DecodedError {
+        #[cfg(feature = "rpc")]
+        if let (Some(pid), Some(rpc_url)) = (program_id, self.rpc_url()) {
+            if anchor::is_anchor_custom_error(code) && self.registry.lookup_program_error(pid, code).is_none() {
+                self.load_idl(pid, rpc_url);
+            }
         }
-        return error;
+
+        let mut candidates = self.candidates(code, program_id).into_iter();
+        let Some(best) = candidates.next() else {
+            let mut error = DecodedError::new(code)
+                .with_name("Unknown".to_string())
+                .with_message("Unknown error code".to_string());
+            if let Some(pid) = program_id {
+                error = error.with_program(pid.to_string());
+            }
+            return error;
+        };
+
+        let mut error = best.error;
+        if best.confidence < Confidence::High {
+            error.alternatives = candidates.collect();
+        }
+        error
+    }
+
+    /// Decode every error reported in a transaction's log messages.
+    pub fn explain_logs(&self, logs: &[String]) -> Vec<DecodedError> {
+        transaction::decode_log_errors(logs, self)
+    }
+
+    /// Every plausible reading of `code`, most likely first, with how
+    /// confident each is and why. See [`decode_candidates`].
+    pub fn candidates(&self, code: u32, program_id: Option<&str>) -> Vec<Candidate> {
+        candidates_in(&self.registry, code, program_id)
+    }
+
+    /// Find errors by name or message across every known program and the IDL
+    /// cache, best match first.
+    pub fn search(&self, query: &str) -> Vec<ErrorMatch> {
+        search::search_errors(query, &self.registry, &IdlCache::open())
+    }
+
+    /// Decode the events programs logged with `emit!`, using their IDLs.
+    pub fn explain_events(&self, logs: &[String]) -> Vec<DecodedEvent> {
+        events::decode_log_events(logs, self)
+    }
+
+    /// Decode top-level instruction `index` of a message, using its program's
+    /// IDL. `loaded` resolves accounts a v0 message takes from lookup tables.
+    pub fn explain_instruction(
+        &self,
+        message: &VersionedMessage,
+        loaded: Option<&LoadedAddresses>,
+        index: u8,
+    ) -> Option<DecodedInstruction> {
+        instruction::decode_message_instruction(message, loaded, index, self)
+    }
+
+    /// Rebuild the program invocation (CPI) tree from log messages.
+    pub fn invocation_tree(&self, logs: &[String]) -> Vec<Invocation> {
+        build_invocation_tree(logs)
+    }
+
+    /// Fetch a landed transaction and decode its errors.
+    #[cfg(feature = "rpc")]
+    pub fn explain_transaction(&self, signature: &str) -> Result<TransactionReport> {
+        rpc::fetch_transaction_report(self, signature)
     }
 
-    // 4. Return raw code for unknown errors
-    let mut error = DecodedError::new(code)
-        .with_name("Unknown".to_string())
-        .with_message("Unknown error code".to_string());
+    /// Decode a transaction saved as JSON (see
+    /// [`rpc::transaction_report_from_json`]) without fetching it.
+    #[cfg(feature = "rpc")]
+    pub fn explain_transaction_json(&self, json: &str) -> Result<TransactionReport> {
+        rpc::transaction_report_from_json(json, self)
+    }
+
+    /// The IDL known for `program_id`, fetching it over RPC if there is none
+    /// yet and the decoder has an RPC URL.
+    pub fn program_idl(&self, program_id: &str) -> Option<Arc<Idl>> {
+        if let Some(idl) = self.registry.get_idl(program_id) {
+            return Some(idl);
+        }
+
+        #[cfg(feature = "rpc")]
+        if let Some(rpc_url) = self.rpc_url() {
+            return self.load_idl(program_id, rpc_url);
+        }
 
-    if let Some(pid) = program_id {
-        error = error.with_program(pid.to_string());
+        None
     }
 
-    error
+    #[cfg(feature = "rpc")]
+    fn load_idl(&self, program_id: &str, rpc_url: &str) -> Option<Arc<Idl>> {
+        if !self.registry.first_idl_attempt(program_id) {
+            return None;
+        }
+
+        let pubkey = Pubkey::from_str(program_id).ok()?;
+
+        // A missing IDL is expected for non-Anchor programs; fall back silently.
+        let idl = idl::cache::load_or_fetch(rpc_url, &pubkey).ok()?;
+
+        // Errors from the built-in registry or user databases take precedence;
+        // the IDL only fills in codes they don't cover.
+        self.registry.fill_idl_errors(program_id, &idl);
+        self.registry.store_idl(program_id, idl);
+        self.registry.get_idl(program_id)
+    }
+}
+
+/// Decode `code` to its most likely meaning with the process-wide registry.
+/// Unless the answer is certain, the other candidates from
+/// [`decode_candidates`] are attached as `alternatives`.
+pub fn decode_error(code: u32, program_id: Option<&str>) -> DecodedError {
+    Decoder::shared().decode(code, program_id)
+}
+
+/// Every plausible reading of `code` in the process-wide registry, most
+/// likely first.
+///
+/// With a `program_id`, the program's own errors win and the Anchor framework
+/// table only applies if the program doesn't define the code. Without one,
+/// the same code can mean different things in different programs, so every
+/// known program defining it is listed, along with the Anchor framework and
+/// runtime errors it could also be.
+pub fn decode_candidates(code: u32, program_id: Option<&str>) -> Vec<Candidate> {
+    candidates_in(&registry::global(), code, program_id)
+}
+
+fn candidates_in(registry: &Registry, code: u32, program_id: Option<&str>) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+    let anchor = registry.lookup_anchor_error(code);
+
+    match program_id {
+        Some(pid) => {
+            let own = registry.lookup_program_error(pid, code);
+            let name = registry.get_program_name(pid).unwrap_or_else(|| pid.to_string());
+
+            if let Some(error) = &own {
+                candidates.push(Candidate::new(error.clone(), Confidence::High, format!("defined by {}", name)));
+            }
+            if let Some(error) = anchor {
+                // Programs like SPL Token never return Anchor's codes, so the
+                // reading is only a fallback for them.
+                let (confidence, reason) = if own.is_some() || registry.is_native_program(pid) {
+                    (
+                        Confidence::Low,
+                        format!("Anchor framework error; only if {} is an Anchor program", name),
+                    )
+                } else {
+                    (Confidence::High, "Anchor framework error".to_string())
+                };
+                candidates.push(Candidate::new(error, confidence, reason));
+            }
+            if candidates.is_empty() && anchor::is_anchor_custom_error(code) {
+                candidates.push(Candidate::new(
+                    anchor::decode_anchor_custom(code).with_program(pid.to_string()),
+                    Confidence::Medium,
+                    format!("custom error #{} of an Anchor program", code - anchor::ANCHOR_ERROR_OFFSET),
+                ));
+            }
+        }
+        None => {
+            let programs = registry.programs_defining(code);
+
+            if let Some(error) = anchor {
+                candidates.push(Candidate::new(
+                    error,
+                    Confidence::Medium,
+                    "Anchor framework error (codes 100-5999)",
+                ));
+            }
+
+            // A single program defining the code is a decent guess; several
+            // mean the program has to be known to tell them apart.
+            let confidence = if programs.len() == 1 && candidates.is_empty() {
+                Confidence::Medium
+            } else {
+                Confidence::Low
+            };
+            for error in programs {
+                let name = registry.get_program_name(&error.program_id).unwrap_or_default();
+                candidates.push(Candidate::new(error, confidence, format!("defined by {}", name)));
+            }
+
+            if anchor::is_anchor_custom_error(code) {
+                let confidence = if candidates.is_empty() { Confidence::Medium } else { Confidence::Low };
+                candidates.push(Candidate::new(
+                    anchor::decode_anchor_custom(code),
+                    confidence,
+                    format!("custom error #{} of an unknown Anchor program", code - anchor::ANCHOR_ERROR_OFFSET),
+                ));
+            }
+
+            if let Some(error) = runtime::decode_instruction_error_code(code) {
+                candidates.push(Candidate::new(
+                    error,
+                    Confidence::Low,
+                    "built-in runtime error, if the code is an InstructionError variant",
+                ));
+            }
+        }
+    }
+
+    // Stable, so equally confident candidates keep the order above.
+    candidates.sort_by_key(|c| std::cmp::Reverse(c.confidence));
+    candidates
+}
+
+/// Like [`decode_error`], but when the registry doesn't know the code for the
+/// program and it is a custom program error, fetch the program's on-chain Anchor IDL and
+/// use its `errors` list to resolve the name and message. Without an `rpc_url`
+/// (or the `rpc` feature) this is just [`decode_error`].
+pub fn decode_error_with_idl(code: u32, program_id: Option<&str>, rpc_url: Option<&str>) -> DecodedError {
+    Decoder::shared_with(rpc_url).decode(code, program_id)
+}
+
+/// The IDL known for `program_id` in the process-wide registry, fetching it
+/// over RPC if there is none yet and an `rpc_url` is given.
+pub fn program_idl(program_id: &str, rpc_url: Option<&str>) -> Option<Arc<Idl>> {
+    Decoder::shared_with(rpc_url).program_idl(program_id)
+}
+
+pub fn parse_program_id(input: &str) -> Result<Pubkey> {
+    Pubkey::from_str(input).map_err(|e| OhsolError::InvalidProgramId(format!("{}: {}", input, e)))
 }
 
-pub fn parse_program_id(input: &str) -> anyhow::Result<Pubkey> {
-    Pubkey::from_str(input).map_err(|e| anyhow::anyhow!("Invalid program ID: {}", e))
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPL_TOKEN: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+    const UNKNOWN_PROGRAM: &str = "9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin";
+
+    #[test]
+    fn test_program_errors_win_over_other_readings() {
+        let error = decode_error(1, Some(SPL_TOKEN));
+        assert_eq!(error.error_name.as_deref(), Some("InsufficientFunds"));
+        assert!(error.alternatives.is_empty());
+
+        // SPL Token isn't an Anchor program, so Anchor's reading of a code it
+        // doesn't define is only a guess.
+        let candidates = decode_candidates(2006, Some(SPL_TOKEN));
+        assert_eq!(candidates[0].error.error_name.as_deref(), Some("ConstraintSeeds"));
+        assert_eq!(candidates[0].confidence, Confidence::Low);
+        assert!(candidates[0].reason.contains("only if"));
+
+        let candidates = decode_candidates(2006, Some(UNKNOWN_PROGRAM));
+        assert_eq!(candidates[0].error.error_name.as_deref(), Some("ConstraintSeeds"));
+        assert_eq!(candidates[0].confidence, Confidence::High);
+    }
+
+    #[test]
+    fn test_ambiguous_code_lists_candidates() {
+        let candidates = decode_candidates(1, None);
+        assert!(candidates.iter().all(|c| c.confidence < Confidence::High));
+        assert!(candidates.iter().any(|c| c.error.program_id == SPL_TOKEN));
+        assert!(candidates
+            .iter()
+            .any(|c| c.error.error_name.as_deref() == Some("InvalidArgument")));
+
+        let error = decode_error(1, None);
+        assert_eq!(error.alternatives.len(), candidates.len() - 1);
+    }
+
+    #[test]
+    fn test_decoders_keep_their_own_registry() {
+        let program_id = UNKNOWN_PROGRAM;
+        let idl = crate::idl::parse_idl(
+            r#"{
+                "version": "0.1.0", "name": "vault", "instructions": [],
+                "errors": [{ "code": 6000, "name": "VaultLocked", "msg": "Vault is locked" }]
+            }"#,
+        )
+        .unwrap();
+
+        let decoder = Decoder::new();
+        decoder.register_idl(program_id, &idl);
+        let error = decoder.clone().decode(6000, Some(program_id));
+        assert_eq!(error.error_name.as_deref(), Some("VaultLocked"));
+
+        let error = Decoder::new().decode(6000, Some(program_id));
+        assert_ne!(error.error_name.as_deref(), Some("VaultLocked"));
+        assert!(registry::lookup_program_error(program_id, 6000).is_none());
+    }
 }
@@ -1,105 +1,515 @@
-use crate::output::DecodedError;
+use crate::idl::Idl;
+use crate::{DecodedError, OhsolError, Result};
 use serde::Deserialize;
 use std::collections::HashMap;
-use std::sync::LazyLock;
+use std::path::Path;
+use std::sync::{Arc, LazyLock, RwLock};
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 pub struct ErrorEntry {
     pub code: u32,
     pub name: String,
+    #[serde(default)]
     pub msg: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 pub struct ProgramErrors {
+    #[serde(default)]
     pub name: String,
     pub errors: Vec<ErrorEntry>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Default, Clone)]
 pub struct ErrorDatabase {
+    #[serde(default)]
     pub programs: HashMap<String, ProgramErrors>,
+    #[serde(default)]
     pub anchor_errors: Vec<ErrorEntry>,
 }
 
-static ERROR_DB: LazyLock<ErrorDatabase> = LazyLock::new(|| {
+impl ErrorDatabase {
+    /// Merge `other` into this database. Entries from `other` win: an error
+    /// code it defines replaces the existing one, other codes are kept.
+    pub fn merge(&mut self, other: ErrorDatabase) {
+        for (program_id, incoming) in other.programs {
+            match self.programs.get_mut(&program_id) {
+                Some(existing) => {
+                    if !incoming.name.is_empty() {
+                        existing.name = incoming.name;
+                    }
+                    merge_entries(&mut existing.errors, incoming.errors);
+                }
+                None => {
+                    self.programs.insert(program_id, incoming);
+                }
+            }
+        }
+
+        merge_entries(&mut self.anchor_errors, other.anchor_errors);
+    }
+}
+
+fn merge_entries(existing: &mut Vec<ErrorEntry>, incoming: Vec<ErrorEntry>) {
+    for entry in incoming {
+        match existing.iter_mut().find(|e| e.code == entry.code) {
+            Some(slot) => *slot = entry,
+            None => existing.push(entry),
+        }
+    }
+    existing.sort_by_key(|e| e.code);
+}
+
+/// The registry shipped with ohsol, parsed once.
+static BUILTIN: LazyLock<ErrorDatabase> = LazyLock::new(|| {
     let json_data = include_str!("../../data/errors.json");
     serde_json::from_str(json_data).expect("Failed to parse error database")
 });
 
-pub fn lookup_program_error(program_id: &str, code: u32) -> Option<DecodedError> {
-    let db = &*ERROR_DB;
-
-    if let Some(program) = db.programs.get(program_id) {
-        if let Some(error) = program.errors.iter().find(|e| e.code == code) {
-            return Some(
-                DecodedError::new(code)
-                    .with_program(program_id.to_string())
-                    .with_name(error.name.clone())
-                    .with_message(error.msg.clone()),
-            );
-        }
-    }
+/// The registry behind the free functions of this module and of
+/// [`crate::decoder`], shared by the whole process.
+static GLOBAL: LazyLock<Arc<Registry>> = LazyLock::new(|| Arc::new(Registry::builtin()));
 
-    None
+/// Error definitions and IDLs of the programs a decoder knows: the built-in
+/// registry plus whatever was loaded into it.
+#[derive(Debug)]
+pub struct Registry {
+    errors: RwLock<ErrorDatabase>,
+    /// Full IDLs of programs known locally or fetched, used to decode events
+    /// and instructions. Kept apart from `errors`, which only needs the errors.
+    idls: RwLock<HashMap<String, Arc<Idl>>>,
+    /// Programs we already tried to fetch an IDL for, so a transaction with
+    /// many errors from the same program only hits RPC once.
+    #[cfg(feature = "rpc")]
+    idl_attempts: std::sync::Mutex<std::collections::HashSet<String>>,
 }
 
-pub fn lookup_anchor_error(code: u32) -> Option<DecodedError> {
-    let db = &*ERROR_DB;
+impl Default for Registry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
 
-    if let Some(error) = db.anchor_errors.iter().find(|e| e.code == code) {
-        return Some(
+impl Registry {
+    /// A registry holding only the built-in programs.
+    pub fn builtin() -> Self {
+        Self {
+            errors: RwLock::new(BUILTIN.clone()),
+            idls: RwLock::new(HashMap::new()),
+            #[cfg(feature = "rpc")]
+            idl_attempts: Default::default(),
+        }
+    }
+
+    pub fn lookup_program_error(&self, program_id: &str, code: u32) -> Option<DecodedError> {
+        let db = self.errors.read().unwrap();
+        let error = db.programs.get(program_id)?.errors.iter().find(|e| e.code == code)?;
+        Some(
+            DecodedError::new(code)
+                .with_program(program_id.to_string())
+                .with_name(error.name.clone())
+                .with_message(error.msg.clone()),
+        )
+    }
+
+    pub fn lookup_anchor_error(&self, code: u32) -> Option<DecodedError> {
+        let db = self.errors.read().unwrap();
+        let error = db.anchor_errors.iter().find(|e| e.code == code)?;
+        Some(
             DecodedError::new(code)
                 .with_program("Anchor Framework".to_string())
                 .with_name(error.name.clone())
                 .with_message(error.msg.clone()),
-        );
+        )
     }
 
-    None
-}
+    /// Every error of a program, given its ID or registry name.
+    pub fn list_program_errors(&self, program_id_or_name: &str) -> Option<Vec<DecodedError>> {
+        let db = self.errors.read().unwrap();
 
-pub fn list_program_errors(program_id_or_name: &str) -> Option<Vec<DecodedError>> {
-    let db = &*ERROR_DB;
+        // Try as program ID first, then as program name.
+        let (pid, program) = db.programs.get_key_value(program_id_or_name).or_else(|| {
+            db.programs
+                .iter()
+                .find(|(_, program)| program.name == program_id_or_name)
+        })?;
 
-    // Try as program ID first
-    if let Some(program) = db.programs.get(program_id_or_name) {
-        return Some(
+        Some(
             program
                 .errors
                 .iter()
                 .map(|e| {
                     DecodedError::new(e.code)
-                        .with_program(program_id_or_name.to_string())
+                        .with_program(pid.clone())
                         .with_name(e.name.clone())
                         .with_message(e.msg.clone())
                 })
                 .collect(),
-        );
+        )
     }
 
-    // Try as program name
-    for (pid, program) in db.programs.iter() {
-        if program.name == program_id_or_name {
-            return Some(
-                program
+    /// Every registered program that defines `code`, ordered by program name.
+    pub fn programs_defining(&self, code: u32) -> Vec<DecodedError> {
+        let db = self.errors.read().unwrap();
+
+        let mut matches: Vec<(&str, DecodedError)> = db
+            .programs
+            .iter()
+            .filter_map(|(program_id, program)| {
+                let error = program.errors.iter().find(|e| e.code == code)?;
+                Some((
+                    program.name.as_str(),
+                    DecodedError::new(code)
+                        .with_program(program_id.clone())
+                        .with_name(error.name.clone())
+                        .with_message(error.msg.clone()),
+                ))
+            })
+            .collect();
+
+        matches.sort_by(|a, b| (a.0, &a.1.program_id).cmp(&(b.0, &b.1.program_id)));
+        matches.into_iter().map(|(_, error)| error).collect()
+    }
+
+    /// Run `f` with read access to the merged error database.
+    pub fn with_database<R>(&self, f: impl FnOnce(&ErrorDatabase) -> R) -> R {
+        f(&self.errors.read().unwrap())
+    }
+
+    pub fn get_program_name(&self, program_id: &str) -> Option<String> {
+        self.errors.read().unwrap().programs.get(program_id).map(|p| p.name.clone())
+    }
+
+    pub fn has_program(&self, program_id: &str) -> bool {
+        self.errors.read().unwrap().programs.contains_key(program_id)
+    }
+
+    /// Whether `program_id` is known and known not to be an Anchor program:
+    /// it has no IDL and defines codes below Anchor's custom range.
+    pub fn is_native_program(&self, program_id: &str) -> bool {
+        if self.get_idl(program_id).is_some() {
+            return false;
+        }
+        let db = self.errors.read().unwrap();
+        db.programs.get(program_id).is_some_and(|program| {
+            program
+                .errors
+                .iter()
+                .any(|e| !super::anchor::is_anchor_custom_error(e.code))
+        })
+    }
+
+    /// Register the errors declared in an Anchor IDL so later lookups for
+    /// `program_id` resolve by name. Replaces any previous entry for the program.
+    pub fn register_idl_errors(&self, program_id: &str, idl: &Idl) {
+        self.errors
+            .write()
+            .unwrap()
+            .programs
+            .insert(program_id.to_string(), idl_program_errors(idl));
+    }
+
+    /// Add the errors declared in an Anchor IDL for codes the registry doesn't
+    /// already define for `program_id`, keeping existing entries.
+    pub fn fill_idl_errors(&self, program_id: &str, idl: &Idl) {
+        let mut db = self.errors.write().unwrap();
+        let incoming = idl_program_errors(idl);
+        match db.programs.get_mut(program_id) {
+            Some(existing) => {
+                let missing: Vec<ErrorEntry> = incoming
                     .errors
-                    .iter()
-                    .map(|e| {
-                        DecodedError::new(e.code)
-                            .with_program(pid.clone())
-                            .with_name(e.name.clone())
-                            .with_message(e.msg.clone())
-                    })
-                    .collect(),
-            );
+                    .into_iter()
+                    .filter(|e| existing.errors.iter().all(|known| known.code != e.code))
+                    .collect();
+                merge_entries(&mut existing.errors, missing);
+            }
+            None => {
+                db.programs.insert(program_id.to_string(), incoming);
+            }
         }
     }
 
-    None
+    /// Keep `idl` for decoding `program_id`'s events and instructions. Does not
+    /// touch its errors; see [`Registry::register_idl_errors`].
+    pub fn store_idl(&self, program_id: &str, idl: Idl) {
+        self.idls.write().unwrap().insert(program_id.to_string(), Arc::new(idl));
+    }
+
+    pub fn get_idl(&self, program_id: &str) -> Option<Arc<Idl>> {
+        self.idls.read().unwrap().get(program_id).cloned()
+    }
+
+    /// Record an attempt to fetch `program_id`'s IDL; false if one was
+    /// already made.
+    #[cfg(feature = "rpc")]
+    pub(crate) fn first_idl_attempt(&self, program_id: &str) -> bool {
+        self.idl_attempts.lock().unwrap().insert(program_id.to_string())
+    }
+
+    /// Merge the error database at `path` into the registry, overriding
+    /// built-in entries. A directory loads every `*.json` file in it, in name
+    /// order. Returns the number of files loaded.
+    pub fn load_error_db(&self, path: &Path) -> Result<usize> {
+        let files = if path.is_dir() {
+            let mut files: Vec<_> = std::fs::read_dir(path)
+                .map_err(|e| OhsolError::invalid_file(path, e))?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "json"))
+                .collect();
+            files.sort();
+            files
+        } else {
+            vec![path.to_path_buf()]
+        };
+
+        for file in &files {
+            match read_error_source(file)? {
+                ErrorSource::Database(db) => self.errors.write().unwrap().merge(db),
+                ErrorSource::Idl(program_id, idl) => {
+                    self.errors.write().unwrap().merge(idl_database(program_id.clone(), &idl));
+                    self.store_idl(&program_id, *idl);
+                }
+            }
+        }
+
+        Ok(files.len())
+    }
+}
+
+/// The process-wide registry.
+pub fn global() -> Arc<Registry> {
+    GLOBAL.clone()
+}
+
+pub fn lookup_program_error(program_id: &str, code: u32) -> Option<DecodedError> {
+    GLOBAL.lookup_program_error(program_id, code)
+}
+
+pub fn lookup_anchor_error(code: u32) -> Option<DecodedError> {
+    GLOBAL.lookup_anchor_error(code)
+}
+
+pub fn list_program_errors(program_id_or_name: &str) -> Option<Vec<DecodedError>> {
+    GLOBAL.list_program_errors(program_id_or_name)
+}
+
+/// Every registered program that defines `code`, ordered by program name.
+pub fn programs_defining(code: u32) -> Vec<DecodedError> {
+    GLOBAL.programs_defining(code)
+}
+
+/// Run `f` with read access to the merged error database.
+pub fn with_database<R>(f: impl FnOnce(&ErrorDatabase) -> R) -> R {
+    GLOBAL.with_database(f)
 }
 
 pub fn get_program_name(program_id: &str) -> Option<String> {
-    let db = &*ERROR_DB;
-    db.programs.get(program_id).map(|p| p.name.clone())
+    GLOBAL.get_program_name(program_id)
+}
+
+pub fn has_program(program_id: &str) -> bool {
+    GLOBAL.has_program(program_id)
+}
+
+/// The errors an Anchor IDL declares, as a registry entry.
+pub fn idl_program_errors(idl: &Idl) -> ProgramErrors {
+    ProgramErrors {
+        name: idl.get_name(),
+        errors: idl
+            .errors
+            .iter()
+            .map(|e| ErrorEntry {
+                code: e.code,
+                name: e.name.clone(),
+                msg: e.msg.clone(),
+            })
+            .collect(),
+    }
+}
+
+/// Register an IDL's errors in the process-wide registry. See
+/// [`Registry::register_idl_errors`].
+pub fn register_idl_errors(program_id: &str, idl: &Idl) {
+    GLOBAL.register_idl_errors(program_id, idl)
+}
+
+pub fn fill_idl_errors(program_id: &str, idl: &Idl) {
+    GLOBAL.fill_idl_errors(program_id, idl)
+}
+
+pub fn store_idl(program_id: &str, idl: Idl) {
+    GLOBAL.store_idl(program_id, idl)
+}
+
+pub fn get_idl(program_id: &str) -> Option<Arc<Idl>> {
+    GLOBAL.get_idl(program_id)
+}
+
+/// Read a user error database: either a JSON file in the `errors.json` schema
+/// or an Anchor IDL that records its program address.
+pub fn read_error_db_file(path: &Path) -> Result<ErrorDatabase> {
+    Ok(match read_error_source(path)? {
+        ErrorSource::Database(db) => db,
+        ErrorSource::Idl(program_id, idl) => idl_database(program_id, &idl),
+    })
+}
+
+enum ErrorSource {
+    Database(ErrorDatabase),
+    Idl(String, Box<Idl>),
+}
+
+fn idl_database(program_id: String, idl: &Idl) -> ErrorDatabase {
+    let mut db = ErrorDatabase::default();
+    db.programs.insert(program_id, idl_program_errors(idl));
+    db
+}
+
+fn read_error_source(path: &Path) -> Result<ErrorSource> {
+    let invalid = |message: String| OhsolError::invalid_file(path, message);
+
+    let data = std::fs::read_to_string(path).map_err(|e| invalid(e.to_string()))?;
+    let value: serde_json::Value = serde_json::from_str(&data).map_err(|e| invalid(e.to_string()))?;
+
+    if value.get("programs").is_some() || value.get("anchor_errors").is_some() {
+        return serde_json::from_value(value)
+            .map(ErrorSource::Database)
+            .map_err(|e| invalid(format!("Invalid error database: {}", e)));
+    }
+
+    let idl: Idl = serde_json::from_value(value)
+        .map_err(|e| invalid(format!("Not an error database or Anchor IDL: {}", e)))?;
+    let program_id = idl
+        .program_id()
+        .ok_or_else(|| invalid("IDL does not record a program address".to_string()))?
+        .to_string();
+
+    Ok(ErrorSource::Idl(program_id, Box::new(idl)))
+}
+
+/// Merge the error database at `path` into the process-wide registry. See
+/// [`Registry::load_error_db`].
+pub fn load_error_db(path: &Path) -> Result<usize> {
+    GLOBAL.load_error_db(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::idl::parse_idl;
+
+    #[test]
+    fn test_register_idl_errors() {
+        let idl = parse_idl(
+            r#"{
+                "address": "Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS",
+                "metadata": { "name": "vault", "version": "0.1.0", "spec": "0.1.0" },
+                "errors": [{ "code": 6001, "name": "AmountTooLarge", "msg": "Amount too large" }]
+            }"#,
+        )
+        .unwrap();
+
+        let registry = Registry::builtin();
+        let pid = "Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS";
+        registry.register_idl_errors(pid, &idl);
+
+        assert!(registry.has_program(pid));
+        assert_eq!(registry.get_program_name(pid).as_deref(), Some("vault"));
+        let error = registry.lookup_program_error(pid, 6001).unwrap();
+        assert_eq!(error.error_name.as_deref(), Some("AmountTooLarge"));
+    }
+
+    #[test]
+    fn test_fill_idl_errors_keeps_registry_entries() {
+        let whirlpool = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
+        let idl = parse_idl(
+            r#"{
+                "version": "0.1.0", "name": "whirlpool",
+                "errors": [
+                    { "code": 6000, "name": "Renamed", "msg": "renamed" },
+                    { "code": 9999, "name": "NewError", "msg": "added upstream" }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let registry = Registry::builtin();
+        registry.fill_idl_errors(whirlpool, &idl);
+
+        let kept = registry.lookup_program_error(whirlpool, 6000).unwrap();
+        assert_eq!(kept.error_name.as_deref(), Some("InvalidEnum"));
+        let added = registry.lookup_program_error(whirlpool, 9999).unwrap();
+        assert_eq!(added.error_name.as_deref(), Some("NewError"));
+    }
+
+    #[test]
+    fn test_builtin_codes_are_unique() {
+        // The file itself: a lookup would only ever find one of two entries.
+        let db: ErrorDatabase = serde_json::from_str(include_str!("../../data/errors.json")).unwrap();
+        for (program_id, program) in &db.programs {
+            let mut codes: Vec<u32> = program.errors.iter().map(|e| e.code).collect();
+            codes.sort_unstable();
+            codes.dedup();
+            assert_eq!(codes.len(), program.errors.len(), "duplicate code in {}", program_id);
+        }
+    }
+
+    #[test]
+    fn test_merge_overrides_per_code() {
+        let mut db: ErrorDatabase = serde_json::from_str(
+            r#"{ "programs": { "P1": { "name": "one", "errors": [
+                { "code": 1, "name": "A", "msg": "a" },
+                { "code": 2, "name": "B", "msg": "b" }
+            ] } }, "anchor_errors": [] }"#,
+        )
+        .unwrap();
+
+        db.merge(
+            serde_json::from_str(
+                r#"{ "programs": {
+                    "P1": { "errors": [{ "code": 2, "name": "B2" }, { "code": 3, "name": "C" }] },
+                    "P2": { "name": "two", "errors": [] }
+                } }"#,
+            )
+            .unwrap(),
+        );
+
+        let p1 = &db.programs["P1"];
+        assert_eq!(p1.name, "one");
+        let names: Vec<_> = p1.errors.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["A", "B2", "C"]);
+        assert!(db.programs.contains_key("P2"));
+    }
+
+    #[test]
+    fn test_load_error_db_directory() {
+        let dir = std::env::temp_dir().join(format!("ohsol-errors-db-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("a_legacy_idl.json"),
+            r#"{
+                "version": "0.1.0", "name": "escrow",
+                "errors": [{ "code": 6000, "name": "Expired", "msg": "Offer expired" }],
+                "metadata": { "address": "3yTKSCKoDcjBFpbgxyJUh4cM1NG77gFXBimkVBx2hKrf" }
+            }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("b_overrides.json"),
+            r#"{ "programs": { "3yTKSCKoDcjBFpbgxyJUh4cM1NG77gFXBimkVBx2hKrf": {
+                "errors": [{ "code": 6000, "name": "OfferExpired", "msg": "The offer has expired" }]
+            } } }"#,
+        )
+        .unwrap();
+        std::fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let registry = Registry::builtin();
+        assert_eq!(registry.load_error_db(&dir).unwrap(), 2);
+        std::fs::remove_dir_all(&dir).ok();
+
+        let pid = "3yTKSCKoDcjBFpbgxyJUh4cM1NG77gFXBimkVBx2hKrf";
+        assert_eq!(registry.get_program_name(pid).as_deref(), Some("escrow"));
+        let error = registry.lookup_program_error(pid, 6000).unwrap();
+        assert_eq!(error.error_name.as_deref(), Some("OfferExpired"));
+    }
 }
@@ -0,0 +1,262 @@
+use super::Decoder;
+use crate::DecodedError;
+use serde::Deserialize;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::transaction::TransactionError;
+use std::sync::LazyLock;
+
+pub const RUNTIME_PROGRAM: &str = "Solana Runtime";
+
+/// A built-in runtime error, keyed by its `InstructionError` or
+/// `TransactionError` variant name.
+#[derive(Deserialize, Debug)]
+pub struct RuntimeErrorEntry {
+    pub code: u32,
+    pub name: String,
+    /// The runtime's own description, as it appears in `Program X failed: ...` logs.
+    pub msg: String,
+    pub explanation: String,
+    #[serde(default)]
+    pub causes: Vec<String>,
+    /// Other log messages that map to this error, such as VM errors.
+    #[serde(default)]
+    pub patterns: Vec<String>,
+}
+
+impl RuntimeErrorEntry {
+    fn to_decoded(&self) -> DecodedError {
+        DecodedError::new(self.code)
+            .with_name(self.name.clone())
+            .with_message(self.msg.clone())
+            .with_explanation(self.explanation.clone(), self.causes.clone())
+    }
+
+    fn matches_message(&self, message: &str) -> bool {
+        message.starts_with(&self.msg)
+            || self
+                .patterns
+                .iter()
+                .any(|p| message.starts_with(p.as_str()))
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RuntimeErrorDatabase {
+    pub instruction_errors: Vec<RuntimeErrorEntry>,
+    pub transaction_errors: Vec<RuntimeErrorEntry>,
+}
+
+static RUNTIME_DB: LazyLock<RuntimeErrorDatabase> = LazyLock::new(|| {
+    let json_data = include_str!("../../data/runtime_errors.json");
+    serde_json::from_str(json_data).expect("Failed to parse runtime error database")
+});
+
+pub fn lookup_instruction_error(name: &str) -> Option<&'static RuntimeErrorEntry> {
+    RUNTIME_DB
+        .instruction_errors
+        .iter()
+        .find(|e| e.name == name)
+}
+
+pub fn lookup_transaction_error(name: &str) -> Option<&'static RuntimeErrorEntry> {
+    RUNTIME_DB
+        .transaction_errors
+        .iter()
+        .find(|e| e.name == name)
+}
+
+/// Decode a built-in `InstructionError` by its variant number.
+pub fn decode_instruction_error_code(code: u32) -> Option<DecodedError> {
+    RUNTIME_DB
+        .instruction_errors
+        .iter()
+        .find(|e| e.code == code)
+        .map(|entry| entry.to_decoded().with_program(RUNTIME_PROGRAM.to_string()))
+}
+
+/// Decode a built-in error by its variant name, e.g. the `Error Code` of an
+/// Anchor `ProgramError` log.
+pub fn decode_error_name(name: &str) -> Option<DecodedError> {
+    lookup_instruction_error(name).map(RuntimeErrorEntry::to_decoded)
+}
+
+/// Decode the message of a `Program X failed: <message>` log line when it is a
+/// built-in runtime error rather than a custom program error.
+pub fn decode_failure_message(message: &str) -> Option<DecodedError> {
+    let message = message.trim();
+    if message.starts_with("custom program error") {
+        return None;
+    }
+
+    RUNTIME_DB
+        .instruction_errors
+        .iter()
+        .find(|e| e.matches_message(message))
+        .map(|entry| entry.to_decoded().with_message(message.to_string()))
+}
+
+/// Decode a transaction's top-level `meta.err`.
+///
+/// `program_ids` lists the program invoked by each top-level instruction, so an
+/// `InstructionError` can be attributed to the program that failed. Custom
+/// program errors go through the regular decoder; built-in variants are
+/// reported by name with the runtime's description.
+pub fn decode_transaction_error(
+    err: &TransactionError,
+    program_ids: &[String],
+    decoder: &Decoder,
+) -> DecodedError {
+    match err {
+        TransactionError::InstructionError(index, instruction_error) => {
+            let program_id = program_ids.get(*index as usize);
+
+            let error = match instruction_error {
+                InstructionError::Custom(code) => {
+                    decoder.decode(*code, program_id.map(String::as_str))
+                }
+                _ => decode_instruction_error(instruction_error),
+            };
+
+            let error = error.with_instruction_index(*index);
+            match program_id {
+                Some(pid) if error.program_id.is_empty() => error.with_program(pid.clone()),
+                _ => error,
+            }
+        }
+        _ => {
+            let name = variant_name(err);
+            let error = match lookup_transaction_error(&name) {
+                Some(entry) => entry.to_decoded(),
+                None => DecodedError::new(variant_index(err)).with_name(name),
+            };
+            error
+                .with_program(RUNTIME_PROGRAM.to_string())
+                .with_message(err.to_string())
+        }
+    }
+}
+
+/// Decode a built-in (non-custom) `InstructionError`.
+pub fn decode_instruction_error(err: &InstructionError) -> DecodedError {
+    let name = variant_name(err);
+    let error = match lookup_instruction_error(&name) {
+        Some(entry) => entry.to_decoded(),
+        None => DecodedError::new(variant_index(err)).with_name(name),
+    };
+    error.with_message(err.to_string())
+}
+
+/// The variant name of a runtime error, without its payload
+/// (`InsufficientFundsForRent { account_index: 2 }` -> `InsufficientFundsForRent`).
+fn variant_name<T: std::fmt::Debug>(err: &T) -> String {
+    let debug = format!("{:?}", err);
+    debug
+        .split(['(', ' ', '{'])
+        .next()
+        .unwrap_or(&debug)
+        .to_string()
+}
+
+/// The variant's wire discriminant, which is what the runtime error "code" is
+/// when it's serialized with bincode.
+fn variant_index<T: serde::Serialize>(err: &T) -> u32 {
+    bincode::serialize(err)
+        .ok()
+        .and_then(|bytes| {
+            bytes
+                .get(..4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        })
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_builtin_instruction_error() {
+        let err = TransactionError::InstructionError(2, InstructionError::InvalidAccountData);
+        let programs = vec![
+            "ComputeBudget111111111111111111111111111111".to_string(),
+            "ComputeBudget111111111111111111111111111111".to_string(),
+            "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA".to_string(),
+        ];
+
+        let error = decode_transaction_error(&err, &programs, &Decoder::new());
+        assert_eq!(error.error_name.as_deref(), Some("InvalidAccountData"));
+        assert_eq!(error.error_code, 3);
+        assert_eq!(error.instruction_index, Some(2));
+        assert_eq!(
+            error.program_id,
+            "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
+        );
+    }
+
+    #[test]
+    fn test_decode_custom_instruction_error() {
+        let err = TransactionError::InstructionError(0, InstructionError::Custom(1));
+        let programs = vec!["TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA".to_string()];
+
+        let error = decode_transaction_error(&err, &programs, &Decoder::new());
+        assert_eq!(error.error_name.as_deref(), Some("InsufficientFunds"));
+        assert_eq!(error.instruction_index, Some(0));
+    }
+
+    #[test]
+    fn test_registry_codes_match_variant_index() {
+        for err in [
+            InstructionError::GenericError,
+            InstructionError::MissingRequiredSignature,
+            InstructionError::Custom(0),
+            InstructionError::ComputationalBudgetExceeded,
+            InstructionError::BuiltinProgramsMustConsumeComputeUnits,
+        ] {
+            let entry = lookup_instruction_error(&variant_name(&err)).unwrap();
+            assert_eq!(entry.code, variant_index(&err), "{}", entry.name);
+        }
+
+        for err in [
+            TransactionError::AccountInUse,
+            TransactionError::CommitCancelled,
+        ] {
+            let entry = lookup_transaction_error(&variant_name(&err)).unwrap();
+            assert_eq!(entry.code, variant_index(&err), "{}", entry.name);
+        }
+    }
+
+    #[test]
+    fn test_decode_failure_message() {
+        let error = decode_failure_message("missing required signature for instruction").unwrap();
+        assert_eq!(
+            error.error_name.as_deref(),
+            Some("MissingRequiredSignature")
+        );
+        assert!(!error.likely_causes.is_empty());
+
+        let error = decode_failure_message("exceeded CUs meter at BPF instruction").unwrap();
+        assert_eq!(
+            error.error_name.as_deref(),
+            Some("ComputationalBudgetExceeded")
+        );
+
+        assert!(decode_failure_message("custom program error: 0x1").is_none());
+        assert!(decode_failure_message("something nobody has seen").is_none());
+    }
+
+    #[test]
+    fn test_decode_transaction_level_error() {
+        let err = TransactionError::InsufficientFundsForRent { account_index: 2 };
+        let error = decode_transaction_error(&err, &[], &Decoder::new());
+        assert_eq!(
+            error.error_name.as_deref(),
+            Some("InsufficientFundsForRent")
+        );
+        assert_eq!(error.instruction_index, None);
+
+        let error =
+            decode_transaction_error(&TransactionError::BlockhashNotFound, &[], &Decoder::new());
+        assert_eq!(error.error_name.as_deref(), Some("BlockhashNotFound"));
+        assert_eq!(error.error_message.as_deref(), Some("Blockhash not found"));
+    }
+}
@@ -0,0 +1,147 @@
+use serde::Serialize;
+use std::path::PathBuf;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, OhsolError>;
+
+/// Why an ohsol operation failed.
+///
+/// Each variant belongs to a category with a stable [`kind`](Self::kind) name
+/// and process [`exit_code`](Self::exit_code), so scripts can tell an
+/// unreachable RPC node from a missing transaction or one that succeeded.
+#[derive(Debug, Error)]
+pub enum OhsolError {
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+
+    #[error("Invalid program ID: {0}")]
+    InvalidProgramId(String),
+
+    #[error("Invalid error code: {0}")]
+    InvalidErrorCode(String),
+
+    #[error("Invalid transaction: {0}")]
+    InvalidTransaction(String),
+
+    /// The RPC or WebSocket endpoint failed or could not be reached.
+    #[error("{0}")]
+    Rpc(String),
+
+    #[error("No RPC URL configured; use Decoder::with_rpc_url")]
+    NoRpcUrl,
+
+    #[error("Unknown profile '{0}'; define it under [profiles] in the config file")]
+    UnknownProfile(String),
+
+    #[error("Transaction not found: {0}")]
+    TransactionNotFound(String),
+
+    #[error("No IDL account found for program {0}")]
+    IdlNotFound(String),
+
+    #[error(
+        "Unknown program: {0}. Try using a program ID or known name like 'spl-token' or 'jupiter'"
+    )]
+    UnknownProgram(String),
+
+    #[error("No known errors match '{0}'")]
+    NoMatches(String),
+
+    /// The transaction exists but reported no errors.
+    #[error("No errors found in transaction")]
+    NoErrors,
+
+    /// An IDL account or file could not be decoded.
+    #[error("{0}")]
+    InvalidIdl(String),
+
+    /// A config file, error database or `Anchor.toml` could not be loaded.
+    #[error("{message}")]
+    InvalidFile { path: PathBuf, message: String },
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+impl OhsolError {
+    pub fn invalid_file(path: impl Into<PathBuf>, message: impl std::fmt::Display) -> Self {
+        let path = path.into();
+        let message = format!("{}: {}", path.display(), message);
+        OhsolError::InvalidFile { path, message }
+    }
+
+    /// Stable, machine-readable category name.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            OhsolError::InvalidSignature(_)
+            | OhsolError::InvalidProgramId(_)
+            | OhsolError::InvalidErrorCode(_)
+            | OhsolError::InvalidTransaction(_) => "invalid_input",
+            OhsolError::Rpc(_) => "rpc",
+            OhsolError::TransactionNotFound(_)
+            | OhsolError::IdlNotFound(_)
+            | OhsolError::UnknownProgram(_)
+            | OhsolError::NoMatches(_) => "not_found",
+            OhsolError::NoErrors => "no_errors",
+            OhsolError::InvalidIdl(_) => "invalid_idl",
+            OhsolError::NoRpcUrl
+            | OhsolError::UnknownProfile(_)
+            | OhsolError::InvalidFile { .. } => "config",
+            OhsolError::Io(_) => "io",
+        }
+    }
+
+    /// Process exit code for this category. `1` is left for failures that
+    /// aren't an `OhsolError`, and `2` matches clap's usage errors.
+    pub fn exit_code(&self) -> u8 {
+        match self.kind() {
+            "invalid_input" => 2,
+            "rpc" => 3,
+            "not_found" => 4,
+            "no_errors" => 5,
+            "invalid_idl" => 6,
+            "config" => 7,
+            "io" => 8,
+            _ => 1,
+        }
+    }
+}
+
+/// The `--json` representation of a failure.
+#[derive(Serialize, Debug)]
+pub struct ErrorReport {
+    pub kind: &'static str,
+    pub message: String,
+    pub exit_code: u8,
+}
+
+impl From<&OhsolError> for ErrorReport {
+    fn from(err: &OhsolError) -> Self {
+        ErrorReport {
+            kind: err.kind(),
+            message: err.to_string(),
+            exit_code: err.exit_code(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_report() {
+        let report = ErrorReport::from(&OhsolError::TransactionNotFound("5h6x".to_string()));
+        assert_eq!(report.kind, "not_found");
+        assert_eq!(report.exit_code, 4);
+        assert_eq!(report.message, "Transaction not found: 5h6x");
+
+        assert_eq!(OhsolError::NoErrors.exit_code(), 5);
+        let io = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied");
+        assert_eq!(OhsolError::from(io).exit_code(), 8);
+        assert_eq!(
+            OhsolError::Rpc("connection refused".to_string()).kind(),
+            "rpc"
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use crate::decoder::Decoder;
+use crate::idl::borsh::BorshReader;
+use crate::log_parser::{walk_logs, LogEntry};
+use base64::Engine;
+use serde::Serialize;
+
+/// An event a program logged with `sol_log_data` (Anchor's `emit!`), decoded
+/// with the program's IDL when possible.
+#[derive(Serialize, Debug, Clone)]
+pub struct DecodedEvent {
+    pub program_id: String,
+    /// Index of the top-level instruction that emitted the event.
+    pub instruction_index: u8,
+    pub cpi_depth: u8,
+    /// Event name from the IDL, or `None` if no IDL event matched.
+    pub name: Option<String>,
+    /// Decoded fields, keyed by name.
+    pub fields: Option<serde_json::Value>,
+    /// Why the fields couldn't be decoded, if the event matched but decoding failed.
+    pub decode_error: Option<String>,
+    pub data_base64: String,
+}
+
+/// Decode every `Program data:` line in a transaction's logs, in order.
+///
+/// Each event is attributed to the program executing when it was logged. If
+/// `decoder` has an RPC URL, IDLs of programs it doesn't know are fetched.
+pub fn decode_log_events(logs: &[String], decoder: &Decoder) -> Vec<DecodedEvent> {
+    let mut events = Vec::new();
+
+    for (frame, entry) in walk_logs(logs) {
+        let LogEntry::Data { chunks } = entry else {
+            continue;
+        };
+        let Some(program_id) = frame.program_id else {
+            continue;
+        };
+
+        let engine = base64::engine::general_purpose::STANDARD;
+        let data: Vec<u8> = chunks
+            .iter()
+            .filter_map(|chunk| engine.decode(chunk).ok())
+            .flatten()
+            .collect();
+
+        let mut event = DecodedEvent {
+            program_id,
+            instruction_index: frame.instruction_index,
+            cpi_depth: frame.cpi_depth,
+            name: None,
+            fields: None,
+            decode_error: None,
+            data_base64: engine.encode(&data),
+        };
+
+        if let Some(idl) = decoder.program_idl(&event.program_id) {
+            if let Some(idl_event) = idl.find_event(&data) {
+                event.name = Some(idl_event.name.clone());
+                match idl.event_fields(idl_event) {
+                    Some(fields) => match BorshReader::new(&idl, &data[8..]).read_fields(&fields) {
+                        Ok(value) => event.fields = Some(value),
+                        Err(e) => event.decode_error = Some(e),
+                    },
+                    None => {
+                        event.decode_error =
+                            Some(format!("no type definition for {}", idl_event.name))
+                    }
+                }
+            }
+        }
+
+        events.push(event);
+    }
+
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::idl::parse_idl;
+
+    #[test]
+    fn test_decode_legacy_event() {
+        let pid = "EvtZ1111111111111111111111111111111111111111";
+        let idl = parse_idl(
+            r#"{
+                "version": "0.1.0", "name": "auction",
+                "events": [{ "name": "BidPlaced", "fields": [
+                    { "name": "bidder", "type": "publicKey", "index": false },
+                    { "name": "amount", "type": "u64", "index": false }
+                ] }]
+            }"#,
+        )
+        .unwrap();
+        let decoder = Decoder::new();
+        decoder.registry().store_idl(pid, idl.clone());
+
+        let mut data = idl.events[0].discriminator();
+        data.extend([0u8; 32]);
+        data.extend(1_500u64.to_le_bytes());
+        let engine = base64::engine::general_purpose::STANDARD;
+
+        let logs: Vec<String> = [
+            format!("Program {} invoke [1]", pid),
+            format!("Program data: {}", engine.encode(&data)),
+            format!("Program data: {}", engine.encode([9u8; 12])),
+            format!("Program {} failed: custom program error: 0x1770", pid),
+        ]
+        .to_vec();
+
+        let events = decode_log_events(&logs, &decoder);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].name.as_deref(), Some("BidPlaced"));
+        assert_eq!(
+            events[0].fields,
+            Some(
+                serde_json::json!({ "bidder": "11111111111111111111111111111111", "amount": 1500 })
+            )
+        );
+        assert_eq!(events[0].program_id, pid);
+        assert_eq!(events[1].name, None);
+    }
+}
@@ -0,0 +1,236 @@
+use super::parse::{Idl, IdlArrayLen, IdlDefinedFields, IdlType, IdlTypeDefTy};
+use base64::Engine;
+use serde_json::{json, Map, Value};
+
+/// Deepest type nesting we follow, so recursive type definitions can't loop.
+const MAX_DEPTH: usize = 32;
+
+/// Reads borsh-encoded values described by IDL types into JSON.
+///
+/// Integers up to 64 bits become JSON numbers; 128-bit integers become strings
+/// so they survive tools that read numbers as doubles. Pubkeys are base58 and
+/// `bytes` are base64.
+pub struct BorshReader<'a> {
+    idl: &'a Idl,
+    data: &'a [u8],
+}
+
+impl<'a> BorshReader<'a> {
+    pub fn new(idl: &'a Idl, data: &'a [u8]) -> Self {
+        Self { idl, data }
+    }
+
+    /// Bytes not consumed yet.
+    pub fn remaining(&self) -> &'a [u8] {
+        self.data
+    }
+
+    pub fn read_fields(&mut self, fields: &IdlDefinedFields) -> Result<Value, String> {
+        self.fields(fields, 0)
+    }
+
+    pub fn read_type(&mut self, ty: &IdlType) -> Result<Value, String> {
+        self.value(ty, 0)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.data.len() < len {
+            return Err(format!(
+                "unexpected end of data (needed {} more bytes)",
+                len - self.data.len()
+            ));
+        }
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn len(&mut self) -> Result<usize, String> {
+        let len = u32::from_le_bytes(self.array()?) as usize;
+        // Every element takes at least a byte in practice; a longer length is
+        // garbage and would otherwise make us allocate for nothing.
+        if len > self.data.len() {
+            return Err(format!("length {} exceeds remaining data", len));
+        }
+        Ok(len)
+    }
+
+    fn fields(&mut self, fields: &IdlDefinedFields, depth: usize) -> Result<Value, String> {
+        match fields {
+            IdlDefinedFields::Named(fields) => {
+                let mut map = Map::new();
+                for field in fields {
+                    let value = self
+                        .value(&field.ty, depth)
+                        .map_err(|e| format!("{}: {}", field.name, e))?;
+                    map.insert(field.name.clone(), value);
+                }
+                Ok(Value::Object(map))
+            }
+            IdlDefinedFields::Tuple(types) => types
+                .iter()
+                .map(|ty| self.value(ty, depth))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Value::Array),
+        }
+    }
+
+    fn value(&mut self, ty: &IdlType, depth: usize) -> Result<Value, String> {
+        if depth > MAX_DEPTH {
+            return Err("type nesting too deep".to_string());
+        }
+
+        match ty {
+            IdlType::Primitive(name) => self.primitive(name),
+            IdlType::Vec { vec } => {
+                let len = self.len()?;
+                (0..len)
+                    .map(|_| self.value(vec, depth + 1))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(Value::Array)
+            }
+            IdlType::Option { option } => match self.take(1)?[0] {
+                0 => Ok(Value::Null),
+                _ => self.value(option, depth + 1),
+            },
+            // `COption` uses a 4-byte tag and always stores the value.
+            IdlType::COption { coption } => {
+                let tag = u32::from_le_bytes(self.array()?);
+                let value = self.value(coption, depth + 1)?;
+                Ok(if tag == 0 { Value::Null } else { value })
+            }
+            IdlType::Array { array: (ty, len) } => {
+                let IdlArrayLen::Value(len) = len else {
+                    return Err("generic array lengths are not supported".to_string());
+                };
+                (0..*len)
+                    .map(|_| self.value(ty, depth + 1))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(Value::Array)
+            }
+            IdlType::Defined { defined } => self.defined(defined.name(), depth + 1),
+            IdlType::Generic { generic } => {
+                Err(format!("generic type {} is not supported", generic))
+            }
+        }
+    }
+
+    fn defined(&mut self, name: &str, depth: usize) -> Result<Value, String> {
+        let idl = self.idl;
+        let def = idl
+            .find_type(name)
+            .ok_or_else(|| format!("type {} is not defined in the IDL", name))?;
+
+        match def {
+            IdlTypeDefTy::Struct { fields: None } => Ok(json!({})),
+            IdlTypeDefTy::Struct {
+                fields: Some(fields),
+            } => self.fields(fields, depth),
+            IdlTypeDefTy::Type { alias } => self.value(alias, depth),
+            IdlTypeDefTy::Enum { variants } => {
+                let index = self.take(1)?[0] as usize;
+                let variant = variants
+                    .get(index)
+                    .ok_or_else(|| format!("invalid variant {} for enum {}", index, name))?;
+                match &variant.fields {
+                    None => Ok(Value::String(variant.name.clone())),
+                    Some(fields) => {
+                        Ok(json!({ variant.name.clone(): self.fields(fields, depth)? }))
+                    }
+                }
+            }
+        }
+    }
+
+    fn primitive(&mut self, name: &str) -> Result<Value, String> {
+        Ok(match name {
+            "bool" => Value::Bool(self.take(1)?[0] != 0),
+            "u8" => json!(self.take(1)?[0]),
+            "i8" => json!(self.take(1)?[0] as i8),
+            "u16" => json!(u16::from_le_bytes(self.array()?)),
+            "i16" => json!(i16::from_le_bytes(self.array()?)),
+            "u32" => json!(u32::from_le_bytes(self.array()?)),
+            "i32" => json!(i32::from_le_bytes(self.array()?)),
+            "u64" => json!(u64::from_le_bytes(self.array()?)),
+            "i64" => json!(i64::from_le_bytes(self.array()?)),
+            "u128" => Value::String(u128::from_le_bytes(self.array()?).to_string()),
+            "i128" => Value::String(i128::from_le_bytes(self.array()?).to_string()),
+            "f32" => json!(f32::from_le_bytes(self.array()?)),
+            "f64" => json!(f64::from_le_bytes(self.array()?)),
+            "pubkey" | "publicKey" => Value::String(bs58::encode(self.take(32)?).into_string()),
+            "string" => {
+                let len = self.len()?;
+                let bytes = self.take(len)?;
+                Value::String(String::from_utf8_lossy(bytes).into_owned())
+            }
+            "bytes" => {
+                let len = self.len()?;
+                Value::String(base64::engine::general_purpose::STANDARD.encode(self.take(len)?))
+            }
+            other => return Err(format!("unsupported type {}", other)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::idl::parse_idl;
+
+    #[test]
+    fn test_read_nested_types() {
+        let idl = parse_idl(
+            r#"{
+                "address": "11111111111111111111111111111111",
+                "metadata": { "name": "t", "version": "0.1.0", "spec": "0.1.0" },
+                "types": [
+                    { "name": "Side", "type": { "kind": "enum", "variants": [
+                        { "name": "Bid" },
+                        { "name": "Ask", "fields": [{ "name": "limit", "type": "u16" }] }
+                    ] } },
+                    { "name": "Order", "type": { "kind": "struct", "fields": [
+                        { "name": "side", "type": { "defined": { "name": "Side" } } },
+                        { "name": "size", "type": "u128" },
+                        { "name": "tags", "type": { "vec": "string" } },
+                        { "name": "memo", "type": { "option": "u8" } },
+                        { "name": "owner", "type": "pubkey" }
+                    ] } }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let mut data = vec![1, 7, 0];
+        data.extend(5u128.to_le_bytes());
+        data.extend(1u32.to_le_bytes());
+        data.extend(2u32.to_le_bytes());
+        data.extend(b"hi");
+        data.push(0);
+        data.extend([0u8; 32]);
+
+        let ty = IdlType::Defined {
+            defined: crate::idl::parse::IdlDefined::Name("Order".to_string()),
+        };
+        let mut reader = BorshReader::new(&idl, &data);
+        let value = reader.read_type(&ty).unwrap();
+
+        assert_eq!(
+            value,
+            json!({
+                "side": { "Ask": { "limit": 7 } },
+                "size": "5",
+                "tags": ["hi"],
+                "memo": null,
+                "owner": "11111111111111111111111111111111"
+            })
+        );
+        assert!(reader.remaining().is_empty());
+
+        let mut short = BorshReader::new(&idl, &data[..10]);
+        assert!(short.read_type(&ty).is_err());
+    }
+}
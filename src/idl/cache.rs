@@ -0,0 +1,305 @@
+use super::parse::Idl;
+use crate::{OhsolError, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "rpc")]
+use super::fetch::fetch_idl_account;
+#[cfg(feature = "rpc")]
+use solana_sdk::pubkey::Pubkey;
+
+pub const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// A cached IDL lookup for one program on one network. Programs without an IDL
+/// account are cached too (`idl: None`) so they don't cost an RPC call per run.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CacheEntry {
+    pub program_id: String,
+    pub network: String,
+    /// Unix timestamp (seconds) of the fetch.
+    pub fetched_at: u64,
+    /// Slot the IDL account was read at.
+    pub slot: u64,
+    /// Decompressed IDL JSON as stored on-chain.
+    pub idl: Option<serde_json::Value>,
+}
+
+impl CacheEntry {
+    pub fn age(&self) -> Duration {
+        Duration::from_secs(now().saturating_sub(self.fetched_at))
+    }
+
+    pub fn is_expired(&self, ttl: Duration) -> bool {
+        self.age() > ttl
+    }
+
+    pub fn parse_idl(&self) -> Option<Result<Idl>> {
+        self.idl.as_ref().map(|value| {
+            Idl::deserialize(value)
+                .map_err(|e| OhsolError::InvalidIdl(format!("Failed to parse cached IDL: {}", e)))
+        })
+    }
+}
+
+/// On-disk IDL cache, laid out as `<dir>/<network>/<program_id>.json`.
+pub struct IdlCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl IdlCache {
+    pub fn new(dir: PathBuf, ttl: Duration) -> Self {
+        Self { dir, ttl }
+    }
+
+    /// Open the cache in the default location. `OHSOL_CACHE_DIR` overrides the
+    /// directory and `OHSOL_IDL_CACHE_TTL` (seconds) overrides the TTL.
+    pub fn open() -> Self {
+        let ttl = std::env::var("OHSOL_IDL_CACHE_TTL")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_TTL);
+
+        Self::new(default_cache_dir().join("idl"), ttl)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn entry_path(&self, network: &str, program_id: &str) -> PathBuf {
+        self.dir.join(network).join(format!("{}.json", program_id))
+    }
+
+    /// Return the entry for `program_id` if present and not expired.
+    pub fn get(&self, network: &str, program_id: &str) -> Option<CacheEntry> {
+        let data = std::fs::read_to_string(self.entry_path(network, program_id)).ok()?;
+        let entry: CacheEntry = serde_json::from_str(&data).ok()?;
+
+        if entry.is_expired(self.ttl) {
+            None
+        } else {
+            Some(entry)
+        }
+    }
+
+    /// Store `entry`. The file is written beside its final path and renamed
+    /// into place, so a concurrent `get` never sees half an entry.
+    pub fn put(&self, entry: &CacheEntry) -> Result<PathBuf> {
+        let path = self.entry_path(&entry.network, &entry.program_id);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension(format!("json.{}.tmp", std::process::id()));
+        std::fs::write(
+            &tmp,
+            serde_json::to_string(entry).map_err(std::io::Error::from)?,
+        )?;
+        std::fs::rename(&tmp, &path).inspect_err(|_| {
+            std::fs::remove_file(&tmp).ok();
+        })?;
+        Ok(path)
+    }
+
+    /// All entries on disk, including expired ones.
+    pub fn list(&self) -> Result<Vec<CacheEntry>> {
+        let mut entries = Vec::new();
+
+        let Ok(networks) = std::fs::read_dir(&self.dir) else {
+            return Ok(entries);
+        };
+
+        for network in networks {
+            let network = network?;
+            if !network.file_type()?.is_dir() {
+                continue;
+            }
+            for file in std::fs::read_dir(network.path())? {
+                let path = file?.path();
+                if path.extension().is_some_and(|ext| ext == "json") {
+                    let data = std::fs::read_to_string(&path)?;
+                    if let Ok(entry) = serde_json::from_str::<CacheEntry>(&data) {
+                        entries.push(entry);
+                    }
+                }
+            }
+        }
+
+        entries.sort_by(|a, b| (&a.network, &a.program_id).cmp(&(&b.network, &b.program_id)));
+        Ok(entries)
+    }
+
+    /// Remove cached entries for `program_id` on every network, or everything
+    /// if no program is given. Returns the number of entries removed.
+    pub fn clear(&self, program_id: Option<&str>) -> Result<usize> {
+        let mut removed = 0;
+
+        for entry in self.list()? {
+            if program_id.is_none_or(|pid| pid == entry.program_id) {
+                std::fs::remove_file(self.entry_path(&entry.network, &entry.program_id))?;
+                removed += 1;
+            }
+        }
+
+        Ok(removed)
+    }
+}
+
+/// `$XDG_CACHE_HOME/ohsol`, falling back to `~/.cache/ohsol`.
+pub fn default_cache_dir() -> PathBuf {
+    if let Ok(dir) = std::env::var("OHSOL_CACHE_DIR") {
+        return PathBuf::from(dir);
+    }
+
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .unwrap_or_else(std::env::temp_dir);
+
+    base.join("ohsol")
+}
+
+/// Name of the cluster an RPC URL points at, used to key cache entries.
+/// Unrecognised endpoints are keyed by host.
+pub fn network_name(rpc_url: &str) -> String {
+    let host = rpc_url
+        .split("://")
+        .nth(1)
+        .unwrap_or(rpc_url)
+        .split(['/', '?'])
+        .next()
+        .unwrap_or_default();
+    let hostname = host.split(':').next().unwrap_or_default();
+
+    if hostname == "localhost" || hostname == "127.0.0.1" || hostname == "0.0.0.0" {
+        "localnet".to_string()
+    } else if hostname.contains("devnet") {
+        "devnet".to_string()
+    } else if hostname.contains("testnet") {
+        "testnet".to_string()
+    } else if hostname.contains("mainnet") {
+        "mainnet".to_string()
+    } else {
+        host.chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    }
+}
+
+/// Fetch a program's IDL account from RPC and store the result in the cache.
+/// A cache that can't be written only costs the next run another fetch, so
+/// that is a warning rather than an error.
+#[cfg(feature = "rpc")]
+pub fn refresh(cache: &IdlCache, rpc_url: &str, program_id: &Pubkey) -> Result<CacheEntry> {
+    let client = crate::rpc::client(rpc_url);
+    let fetched = fetch_idl_account(&client, program_id)?;
+
+    let idl = fetched
+        .json
+        .map(|json| serde_json::from_str(&json))
+        .transpose()
+        .map_err(|e| OhsolError::InvalidIdl(format!("Failed to parse IDL: {}", e)))?;
+
+    let entry = CacheEntry {
+        program_id: program_id.to_string(),
+        network: network_name(rpc_url),
+        fetched_at: now(),
+        slot: fetched.slot,
+        idl,
+    };
+    if let Err(e) = cache.put(&entry) {
+        eprintln!("Warning: couldn't cache the IDL in {}: {}", cache.dir().display(), e);
+    }
+
+    Ok(entry)
+}
+
+/// Return the program's IDL from the cache, fetching it if missing or expired.
+#[cfg(feature = "rpc")]
+pub fn load_or_fetch(rpc_url: &str, program_id: &Pubkey) -> Result<Idl> {
+    let cache = IdlCache::open();
+    let pid = program_id.to_string();
+
+    let entry = match cache.get(&network_name(rpc_url), &pid) {
+        Some(entry) => entry,
+        None => refresh(&cache, rpc_url, program_id)?,
+    };
+
+    entry
+        .parse_idl()
+        .unwrap_or(Err(OhsolError::IdlNotFound(pid)))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_network_name() {
+        assert_eq!(
+            network_name("https://api.mainnet-beta.solana.com"),
+            "mainnet"
+        );
+        assert_eq!(network_name("https://api.devnet.solana.com"), "devnet");
+        assert_eq!(network_name("http://127.0.0.1:8899"), "localnet");
+        assert_eq!(
+            network_name("https://rpc.example.com:443/abc"),
+            "rpc.example.com_443"
+        );
+    }
+
+    #[test]
+    fn test_put_get_clear() {
+        let dir = std::env::temp_dir().join(format!("ohsol-cache-test-{}", std::process::id()));
+        let cache = IdlCache::new(dir.clone(), DEFAULT_TTL);
+
+        let entry = CacheEntry {
+            program_id: "Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS".to_string(),
+            network: "devnet".to_string(),
+            fetched_at: now(),
+            slot: 42,
+            idl: Some(serde_json::json!({ "name": "vault", "version": "0.1.0", "errors": [] })),
+        };
+        let path = cache.put(&entry).unwrap();
+        let files: Vec<_> = std::fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .map(|f| f.unwrap().path())
+            .collect();
+        assert_eq!(files, [path]);
+
+        let cached = cache.get("devnet", &entry.program_id).unwrap();
+        assert_eq!(cached.slot, 42);
+        assert_eq!(cached.parse_idl().unwrap().unwrap().get_name(), "vault");
+        assert!(cache.get("mainnet", &entry.program_id).is_none());
+
+        let expired = IdlCache::new(dir.clone(), Duration::ZERO);
+        let stale = CacheEntry {
+            fetched_at: 0,
+            ..entry.clone()
+        };
+        expired.put(&stale).unwrap();
+        assert!(expired.get("devnet", &entry.program_id).is_none());
+
+        assert_eq!(cache.list().unwrap().len(), 1);
+        assert_eq!(cache.clear(None).unwrap(), 1);
+        assert!(cache.list().unwrap().is_empty());
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
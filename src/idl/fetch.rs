@@ -1,23 +1,37 @@
 use super::parse::{parse_idl, Idl};
+use crate::{OhsolError, Result};
 use flate2::read::ZlibDecoder;
 use solana_client::rpc_client::RpcClient;
 use solana_sdk::pubkey::Pubkey;
 use std::io::Read;
 
+/// Raw result of reading a program's IDL account.
+pub struct FetchedIdl {
+    /// Decompressed IDL JSON, or `None` if the program has no IDL account.
+    pub json: Option<String>,
+    /// Slot the account was read at.
+    pub slot: u64,
+}
+
 pub fn get_idl_address(program_id: &Pubkey) -> Pubkey {
     let (idl_address, _) = Pubkey::find_program_address(&[b"anchor:idl", program_id.as_ref()], program_id);
     idl_address
 }
 
-pub fn fetch_idl(rpc_client: &RpcClient, program_id: &Pubkey) -> anyhow::Result<Idl> {
+pub fn fetch_idl_account(rpc_client: &RpcClient, program_id: &Pubkey) -> Result<FetchedIdl> {
     let idl_address = get_idl_address(program_id);
 
-    let account = rpc_client
-        .get_account(&idl_address)
-        .map_err(|e| anyhow::anyhow!("Failed to fetch IDL account: {}", e))?;
+    let response = rpc_client
+        .get_account_with_commitment(&idl_address, rpc_client.commitment())
+        .map_err(|e| OhsolError::Rpc(format!("Failed to fetch IDL account: {}", e)))?;
+
+    let slot = response.context.slot;
+    let Some(account) = response.value else {
+        return Ok(FetchedIdl { json: None, slot });
+    };
 
     if account.data.len() < 44 {
-        return Err(anyhow::anyhow!("IDL account data too short"));
+        return Err(OhsolError::InvalidIdl("IDL account data too short".to_string()));
     }
 
     // IDL data structure:
@@ -32,12 +46,24 @@ pub fn fetch_idl(rpc_client: &RpcClient, program_id: &Pubkey) -> anyhow::Result<
     let mut decompressed = String::new();
     decoder
         .read_to_string(&mut decompressed)
-        .map_err(|e| anyhow::anyhow!("Failed to decompress IDL: {}", e))?;
+        .map_err(|e| OhsolError::InvalidIdl(format!("Failed to decompress IDL: {}", e)))?;
+
+    Ok(FetchedIdl {
+        json: Some(decompressed),
+        slot,
+    })
+}
+
+pub fn fetch_idl(rpc_client: &RpcClient, program_id: &Pubkey) -> Result<Idl> {
+    let fetched = fetch_idl_account(rpc_client, program_id)?;
+    let json = fetched
+        .json
+        .ok_or_else(|| OhsolError::IdlNotFound(program_id.to_string()))?;
 
-    parse_idl(&decompressed)
+    parse_idl(&json)
 }
 
-pub fn fetch_idl_with_url(rpc_url: &str, program_id: &Pubkey) -> anyhow::Result<Idl> {
-    let client = RpcClient::new(rpc_url.to_string());
+pub fn fetch_idl_with_url(rpc_url: &str, program_id: &Pubkey) -> Result<Idl> {
+    let client = crate::rpc::client(rpc_url);
     fetch_idl(&client, program_id)
 }
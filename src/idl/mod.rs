@@ -1,5 +1,9 @@
+pub mod borsh;
+pub mod cache;
+#[cfg(feature = "rpc")]
 pub mod fetch;
 pub mod parse;
 
+#[cfg(feature = "rpc")]
 pub use fetch::{fetch_idl, fetch_idl_with_url, get_idl_address};
-pub use parse::{parse_idl, Idl, IdlError};
+pub use parse::{parse_idl, Idl, IdlError, IdlEvent, IdlType};
@@ -1,4 +1,7 @@
-use serde::{Deserialize, Serialize};
+use crate::{OhsolError, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use sha2::{Digest, Sha256};
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct IdlError {
@@ -8,15 +11,200 @@ pub struct IdlError {
     pub msg: String,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+/// A field or element type, in either the legacy (`publicKey`,
+/// `{"defined": "Name"}`) or 0.30+ (`pubkey`, `{"defined": {"name": ...}}`) form.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum IdlType {
+    /// `u64`, `bool`, `string`, `pubkey`, `bytes`, ...
+    Primitive(String),
+    Vec { vec: Box<IdlType> },
+    Option { option: Box<IdlType> },
+    COption { coption: Box<IdlType> },
+    Array { array: (Box<IdlType>, IdlArrayLen) },
+    Defined { defined: IdlDefined },
+    Generic { generic: String },
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum IdlArrayLen {
+    Value(usize),
+    Generic { generic: String },
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum IdlDefined {
+    Name(String),
+    Ref { name: String },
+}
+
+impl IdlDefined {
+    pub fn name(&self) -> &str {
+        match self {
+            IdlDefined::Name(name) | IdlDefined::Ref { name } => name,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct IdlField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: IdlType,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum IdlDefinedFields {
+    Named(Vec<IdlField>),
+    Tuple(Vec<IdlType>),
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct IdlEnumVariant {
+    pub name: String,
+    #[serde(default)]
+    pub fields: Option<IdlDefinedFields>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum IdlTypeDefTy {
+    Struct {
+        #[serde(default)]
+        fields: Option<IdlDefinedFields>,
+    },
+    Enum {
+        variants: Vec<IdlEnumVariant>,
+    },
+    Type {
+        alias: IdlType,
+    },
+}
+
+/// An entry of the IDL's `types` section.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct IdlTypeDef {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: IdlTypeDefTy,
+}
+
+/// An entry of the IDL's `events` section. Legacy IDLs list the fields inline;
+/// 0.30+ IDLs give the discriminator and describe the fields in `types`.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct IdlEvent {
+    pub name: String,
+    #[serde(default)]
+    pub discriminator: Vec<u8>,
+    #[serde(default)]
+    pub fields: Vec<IdlField>,
+}
+
+/// An account an instruction takes. Legacy IDLs spell the flags `isMut`,
+/// `isSigner` and `isOptional`.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct IdlInstructionAccount {
+    pub name: String,
+    #[serde(default, alias = "isMut")]
+    pub writable: bool,
+    #[serde(default, alias = "isSigner")]
+    pub signer: bool,
+    #[serde(default, alias = "isOptional")]
+    pub optional: bool,
+}
+
+/// A group of accounts from a nested `Accounts` struct.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct IdlInstructionAccounts {
+    pub name: String,
+    pub accounts: Vec<IdlInstructionAccountItem>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum IdlInstructionAccountItem {
+    Composite(IdlInstructionAccounts),
+    Single(IdlInstructionAccount),
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct IdlInstruction {
+    pub name: String,
+    #[serde(default)]
+    pub discriminator: Vec<u8>,
+    #[serde(default)]
+    pub accounts: Vec<IdlInstructionAccountItem>,
+    #[serde(default)]
+    pub args: Vec<IdlField>,
+    /// Type of the data the instruction returns with `set_return_data`.
+    #[serde(default)]
+    pub returns: Option<IdlType>,
+}
+
+impl IdlInstruction {
+    /// The 8-byte prefix of the instruction data: given explicitly in 0.30+
+    /// IDLs, otherwise `sha256("global:<snake_case_name>")[..8]`.
+    pub fn discriminator(&self) -> Vec<u8> {
+        if self.discriminator.len() == 8 {
+            return self.discriminator.clone();
+        }
+        Sha256::digest(format!("global:{}", to_snake_case(&self.name)).as_bytes())[..8].to_vec()
+    }
+
+    /// Accounts in the order the instruction expects them, with nested groups
+    /// flattened and named `group.account`.
+    pub fn flat_accounts(&self) -> Vec<(String, &IdlInstructionAccount)> {
+        fn flatten<'a>(
+            items: &'a [IdlInstructionAccountItem],
+            prefix: &str,
+            out: &mut Vec<(String, &'a IdlInstructionAccount)>,
+        ) {
+            for item in items {
+                match item {
+                    IdlInstructionAccountItem::Single(account) => {
+                        out.push((format!("{}{}", prefix, account.name), account));
+                    }
+                    IdlInstructionAccountItem::Composite(group) => {
+                        flatten(&group.accounts, &format!("{}{}.", prefix, group.name), out);
+                    }
+                }
+            }
+        }
+
+        let mut out = Vec::new();
+        flatten(&self.accounts, "", &mut out);
+        out
+    }
+}
+
+/// An entry of the IDL's `accounts` section. Legacy IDLs define the account's
+/// layout inline; 0.30+ IDLs give a discriminator and define it in `types`.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct IdlAccountDef {
+    pub name: String,
+    #[serde(default)]
+    pub discriminator: Vec<u8>,
+    #[serde(default, rename = "type")]
+    pub ty: Option<IdlTypeDefTy>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct IdlMetadata {
+    #[serde(default)]
     pub name: String,
+    #[serde(default)]
     pub version: String,
     #[serde(default)]
     pub spec: String,
+    /// Program address as written by legacy `anchor build` (pre-0.30).
+    #[serde(default)]
+    pub address: String,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Idl {
     #[serde(default)]
     pub address: String,
@@ -33,6 +221,32 @@ pub struct Idl {
 
     #[serde(default)]
     pub errors: Vec<IdlError>,
+
+    #[serde(default, deserialize_with = "lenient_vec")]
+    pub instructions: Vec<IdlInstruction>,
+
+    #[serde(default, deserialize_with = "lenient_vec")]
+    pub accounts: Vec<IdlAccountDef>,
+
+    #[serde(default, deserialize_with = "lenient_vec")]
+    pub events: Vec<IdlEvent>,
+
+    #[serde(default, deserialize_with = "lenient_vec")]
+    pub types: Vec<IdlTypeDef>,
+}
+
+/// Deserialize a list, dropping entries ohsol can't model, so one unsupported
+/// type definition doesn't make the rest of the IDL unusable.
+fn lenient_vec<'de, D, T>(deserializer: D) -> std::result::Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    let values = Vec::<serde_json::Value>::deserialize(deserializer)?;
+    Ok(values
+        .into_iter()
+        .filter_map(|value| serde_json::from_value(value).ok())
+        .collect())
 }
 
 impl Idl {
@@ -41,22 +255,108 @@ impl Idl {
     }
 
     pub fn get_name(&self) -> String {
-        if let Some(metadata) = &self.metadata {
-            metadata.name.clone()
-        } else {
-            self.name.clone()
+        match &self.metadata {
+            Some(metadata) if !metadata.name.is_empty() => metadata.name.clone(),
+            _ => self.name.clone(),
+        }
+    }
+
+    /// The program ID this IDL was built for, if it records one.
+    pub fn program_id(&self) -> Option<&str> {
+        if !self.address.is_empty() {
+            return Some(&self.address);
         }
+        self.metadata
+            .as_ref()
+            .map(|m| m.address.as_str())
+            .filter(|address| !address.is_empty())
     }
 
     pub fn get_version(&self) -> String {
-        if let Some(metadata) = &self.metadata {
-            metadata.version.clone()
-        } else {
-            self.version.clone()
+        match &self.metadata {
+            Some(metadata) if !metadata.version.is_empty() => metadata.version.clone(),
+            _ => self.version.clone(),
+        }
+    }
+
+    /// The definition of a named type. Legacy IDLs can also refer to account
+    /// layouts, which are defined in `accounts` rather than `types`.
+    pub fn find_type(&self, name: &str) -> Option<&IdlTypeDefTy> {
+        self.types
+            .iter()
+            .find(|t| t.name == name)
+            .map(|t| &t.ty)
+            .or_else(|| self.accounts.iter().find(|a| a.name == name)?.ty.as_ref())
+    }
+
+    /// The instruction whose discriminator prefixes `data`.
+    pub fn find_instruction(&self, data: &[u8]) -> Option<&IdlInstruction> {
+        let prefix = data.get(..8)?;
+        self.instructions.iter().find(|ix| ix.discriminator() == prefix)
+    }
+
+    /// The event whose discriminator prefixes `data`.
+    pub fn find_event(&self, data: &[u8]) -> Option<&IdlEvent> {
+        let prefix = data.get(..8)?;
+        self.events.iter().find(|e| e.discriminator() == prefix)
+    }
+
+    /// An event's fields, from the event itself (legacy) or its type definition.
+    pub fn event_fields(&self, event: &IdlEvent) -> Option<IdlDefinedFields> {
+        if !event.fields.is_empty() {
+            return Some(IdlDefinedFields::Named(event.fields.clone()));
+        }
+        match self.find_type(&event.name)? {
+            IdlTypeDefTy::Struct { fields } => Some(fields.clone().unwrap_or(IdlDefinedFields::Named(Vec::new()))),
+            _ => None,
+        }
+    }
+}
+
+impl IdlEvent {
+    /// The 8-byte prefix of the event's data: given explicitly in 0.30+ IDLs,
+    /// otherwise `sha256("event:<Name>")[..8]`.
+    pub fn discriminator(&self) -> Vec<u8> {
+        if self.discriminator.len() == 8 {
+            return self.discriminator.clone();
+        }
+        Sha256::digest(format!("event:{}", self.name).as_bytes())[..8].to_vec()
+    }
+}
+
+/// `placeBidV2` -> `place_bid_v2`, the way Anchor derives legacy sighashes.
+fn to_snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut out = String::with_capacity(name.len() + 4);
+
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            if prev.is_lowercase() || prev.is_ascii_digit() || (prev.is_uppercase() && next_is_lower) {
+                out.push('_');
+            }
         }
+        out.extend(c.to_lowercase());
     }
+
+    out
+}
+
+pub fn parse_idl(json: &str) -> Result<Idl> {
+    serde_json::from_str(json).map_err(|e| OhsolError::InvalidIdl(format!("Failed to parse IDL: {}", e)))
 }
 
-pub fn parse_idl(json: &str) -> anyhow::Result<Idl> {
-    serde_json::from_str(json).map_err(|e| anyhow::anyhow!("Failed to parse IDL: {}", e))
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instruction_discriminator() {
+        assert_eq!(to_snake_case("placeBidV2"), "place_bid_v2");
+        assert_eq!(to_snake_case("initialize"), "initialize");
+
+        let ix: IdlInstruction = serde_json::from_str(r#"{ "name": "initialize" }"#).unwrap();
+        assert_eq!(ix.discriminator(), [175, 175, 109, 31, 13, 152, 155, 237]);
+    }
 }
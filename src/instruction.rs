@@ -0,0 +1,205 @@
+use crate::decoder::Decoder;
+use crate::idl::borsh::BorshReader;
+use crate::idl::parse::IdlDefinedFields;
+use base64::Engine;
+use serde::Serialize;
+use solana_sdk::message::v0::LoadedAddresses;
+use solana_sdk::message::{AccountKeys, VersionedMessage};
+
+/// An account passed to an instruction, labeled with the IDL's name for it.
+#[derive(Serialize, Debug, Clone)]
+pub struct DecodedAccount {
+    pub pubkey: String,
+    /// Name from the IDL; `None` for accounts it doesn't list (e.g. remaining accounts).
+    pub name: Option<String>,
+    /// Whether the transaction signed for / write-locked the account.
+    pub signer: bool,
+    pub writable: bool,
+    /// Whether the IDL expects the account to be a signer / writable.
+    pub expected_signer: Option<bool>,
+    pub expected_writable: Option<bool>,
+}
+
+/// A top-level instruction, decoded with its program's IDL when possible.
+#[derive(Serialize, Debug, Clone)]
+pub struct DecodedInstruction {
+    pub index: u8,
+    pub program_id: String,
+    /// Instruction name from the IDL, or `None` if no IDL instruction matched.
+    pub name: Option<String>,
+    /// Decoded arguments, keyed by name.
+    pub args: Option<serde_json::Value>,
+    /// Why the arguments couldn't be decoded, if the instruction matched.
+    pub decode_error: Option<String>,
+    pub accounts: Vec<DecodedAccount>,
+    pub data_base64: String,
+}
+
+/// Decode top-level instruction `index` of `message`. If `decoder` has an RPC
+/// URL, the program's IDL is fetched if it isn't known locally.
+///
+/// `loaded` are the addresses a v0 message loads from lookup tables; without
+/// them, accounts past the static keys are reported as unresolved.
+pub fn decode_message_instruction(
+    message: &VersionedMessage,
+    loaded: Option<&LoadedAddresses>,
+    index: u8,
+    decoder: &Decoder,
+) -> Option<DecodedInstruction> {
+    let instruction = message.instructions().get(index as usize)?;
+    let keys = AccountKeys::new(message.static_account_keys(), loaded);
+
+    let program_id = keys
+        .get(instruction.program_id_index as usize)
+        .map_or_else(|| "unknown".to_string(), |key| key.to_string());
+
+    let mut decoded = DecodedInstruction {
+        index,
+        program_id: program_id.clone(),
+        name: None,
+        args: None,
+        decode_error: None,
+        accounts: instruction
+            .accounts
+            .iter()
+            .map(|&i| {
+                let i = i as usize;
+                DecodedAccount {
+                    pubkey: keys.get(i).map_or_else(
+                        || format!("unresolved (account index {})", i),
+                        |key| key.to_string(),
+                    ),
+                    name: None,
+                    signer: message.is_signer(i),
+                    writable: message.is_maybe_writable(i, None),
+                    expected_signer: None,
+                    expected_writable: None,
+                }
+            })
+            .collect(),
+        data_base64: base64::engine::general_purpose::STANDARD.encode(&instruction.data),
+    };
+
+    let Some(idl) = decoder.program_idl(&program_id) else {
+        return Some(decoded);
+    };
+    let Some(idl_instruction) = idl.find_instruction(&instruction.data) else {
+        return Some(decoded);
+    };
+
+    decoded.name = Some(idl_instruction.name.clone());
+
+    let args = IdlDefinedFields::Named(idl_instruction.args.clone());
+    match BorshReader::new(&idl, &instruction.data[8..]).read_fields(&args) {
+        Ok(value) => decoded.args = Some(value),
+        Err(e) => decoded.decode_error = Some(e),
+    }
+
+    for (account, (name, expected)) in decoded
+        .accounts
+        .iter_mut()
+        .zip(idl_instruction.flat_accounts())
+    {
+        account.name = Some(name);
+        account.expected_signer = Some(expected.signer);
+        account.expected_writable = Some(expected.writable);
+    }
+
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::idl::parse_idl;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::message::{v0, AddressLookupTableAccount, Message};
+    use solana_sdk::pubkey::Pubkey;
+
+    #[test]
+    fn test_decode_legacy_instruction() {
+        let program_id = Pubkey::new_unique();
+        let idl = parse_idl(
+            r#"{
+                "version": "0.1.0", "name": "auction",
+                "instructions": [{
+                    "name": "placeBid",
+                    "accounts": [
+                        { "name": "bidder", "isMut": true, "isSigner": true },
+                        { "name": "market", "accounts": [
+                            { "name": "auction", "isMut": true, "isSigner": false }
+                        ] }
+                    ],
+                    "args": [{ "name": "amount", "type": "u64" }]
+                }]
+            }"#,
+        )
+        .unwrap();
+        let decoder = Decoder::new();
+        decoder
+            .registry()
+            .store_idl(&program_id.to_string(), idl.clone());
+
+        let bidder = Pubkey::new_unique();
+        let auction = Pubkey::new_unique();
+        let mut data = idl.instructions[0].discriminator();
+        data.extend(1_500u64.to_le_bytes());
+
+        let message = VersionedMessage::Legacy(Message::new_with_blockhash(
+            &[Instruction::new_with_bytes(
+                program_id,
+                &data,
+                vec![
+                    AccountMeta::new(bidder, true),
+                    AccountMeta::new_readonly(auction, false),
+                ],
+            )],
+            Some(&bidder),
+            &Hash::default(),
+        ));
+
+        let decoded = decode_message_instruction(&message, None, 0, &decoder).unwrap();
+        assert_eq!(decoded.name.as_deref(), Some("placeBid"));
+        assert_eq!(decoded.args, Some(serde_json::json!({ "amount": 1500 })));
+
+        assert_eq!(decoded.accounts[0].name.as_deref(), Some("bidder"));
+        assert!(decoded.accounts[0].signer && decoded.accounts[0].writable);
+        assert_eq!(decoded.accounts[1].name.as_deref(), Some("market.auction"));
+        assert!(!decoded.accounts[1].writable);
+        assert_eq!(decoded.accounts[1].expected_writable, Some(true));
+    }
+
+    #[test]
+    fn test_resolve_lookup_table_accounts() {
+        let payer = Pubkey::new_unique();
+        let vault = Pubkey::new_unique();
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: vec![Pubkey::new_unique(), vault],
+        };
+        let instruction = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[1, 2, 3],
+            vec![
+                AccountMeta::new(payer, true),
+                AccountMeta::new(vault, false),
+            ],
+        );
+        let message = VersionedMessage::V0(
+            v0::Message::try_compile(&payer, &[instruction], &[table], Hash::default()).unwrap(),
+        );
+
+        let unresolved = decode_message_instruction(&message, None, 0, &Decoder::new()).unwrap();
+        assert!(unresolved.accounts[1].pubkey.starts_with("unresolved"));
+
+        let loaded = LoadedAddresses {
+            writable: vec![vault],
+            readonly: vec![],
+        };
+        let decoded =
+            decode_message_instruction(&message, Some(&loaded), 0, &Decoder::new()).unwrap();
+        assert_eq!(decoded.accounts[1].pubkey, vault.to_string());
+        assert!(decoded.accounts[1].writable && !decoded.accounts[1].signer);
+    }
+}
@@ -0,0 +1,241 @@
+use crate::log_parser::{parse_log_line, LogEntry};
+use serde::Serialize;
+
+/// How a program invocation ended.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Outcome {
+    Success,
+    Failed {
+        error: String,
+    },
+    /// No `success`/`failed` line was logged, e.g. because logs were truncated.
+    Incomplete,
+}
+
+/// One program invocation reconstructed from transaction logs, with the CPIs
+/// it made as children.
+#[derive(Serialize, Debug, Clone)]
+pub struct Invocation {
+    pub program_id: String,
+    pub depth: u8,
+    /// Index of the top-level instruction this invocation belongs to.
+    pub instruction_index: u8,
+    /// Log lines emitted directly by this invocation (not by its CPIs).
+    pub logs: Vec<String>,
+    pub compute_used: Option<u64>,
+    pub compute_total: Option<u64>,
+    pub outcome: Outcome,
+    pub children: Vec<Invocation>,
+}
+
+impl Invocation {
+    fn new(program_id: String, depth: u8, instruction_index: u8) -> Self {
+        Self {
+            program_id,
+            depth,
+            instruction_index,
+            logs: Vec::new(),
+            compute_used: None,
+            compute_total: None,
+            outcome: Outcome::Incomplete,
+            children: Vec::new(),
+        }
+    }
+
+    pub fn is_failed(&self) -> bool {
+        matches!(self.outcome, Outcome::Failed { .. })
+    }
+
+    /// Compute units consumed by CPIs made from this invocation.
+    pub fn children_compute_used(&self) -> u64 {
+        self.children.iter().filter_map(|c| c.compute_used).sum()
+    }
+
+    /// Compute units consumed by this program itself, excluding its CPIs.
+    pub fn self_compute_used(&self) -> Option<u64> {
+        self.compute_used
+            .map(|used| used.saturating_sub(self.children_compute_used()))
+    }
+
+    /// The innermost failed invocation in this subtree.
+    pub fn failing_leaf(&self) -> Option<&Invocation> {
+        if !self.is_failed() {
+            return None;
+        }
+        self.children
+            .iter()
+            .find_map(Invocation::failing_leaf)
+            .or(Some(self))
+    }
+}
+
+/// Rebuild the invocation tree from raw log lines. Returns one root per
+/// top-level instruction.
+pub fn build_invocation_tree(logs: &[String]) -> Vec<Invocation> {
+    let mut roots: Vec<Invocation> = Vec::new();
+    let mut stack: Vec<Invocation> = Vec::new();
+
+    for line in logs {
+        match parse_log_line(line) {
+            LogEntry::Invoke { program_id, depth } => {
+                // A missing success/failed line (truncated logs) leaves stale
+                // frames; unwind them before pushing the new one.
+                while stack.len() >= depth.max(1) as usize {
+                    close_frame(&mut stack, &mut roots, Outcome::Incomplete);
+                }
+                let instruction_index = match stack.first() {
+                    Some(root) => root.instruction_index,
+                    None => roots.len() as u8,
+                };
+                stack.push(Invocation::new(program_id, depth, instruction_index));
+            }
+            LogEntry::Consumed {
+                program_id,
+                used,
+                total,
+            } => {
+                // Agave logs usage before `success`/`failed`; other runtimes log
+                // it after a failure, once the frame is already closed.
+                let frame = match stack.last_mut() {
+                    Some(frame) if frame.program_id == program_id => Some(frame),
+                    Some(parent) => parent.children.last_mut(),
+                    None => roots.last_mut(),
+                };
+                if let Some(frame) =
+                    frame.filter(|f| f.program_id == program_id && f.compute_used.is_none())
+                {
+                    frame.compute_used = Some(used);
+                    frame.compute_total = Some(total);
+                }
+            }
+            LogEntry::Success { program_id } => {
+                if stack.last().is_some_and(|f| f.program_id == program_id) {
+                    close_frame(&mut stack, &mut roots, Outcome::Success);
+                }
+            }
+            LogEntry::Failed { program_id, error } => {
+                if stack.last().is_some_and(|f| f.program_id == program_id) {
+                    close_frame(&mut stack, &mut roots, Outcome::Failed { error });
+                }
+            }
+            LogEntry::Other { message } => {
+                if let Some(frame) = stack.last_mut() {
+                    frame.logs.push(message);
+                }
+            }
+            _ => {
+                if let Some(frame) = stack.last_mut() {
+                    frame.logs.push(line.clone());
+                }
+            }
+        }
+    }
+
+    while !stack.is_empty() {
+        close_frame(&mut stack, &mut roots, Outcome::Incomplete);
+    }
+
+    roots
+}
+
+fn close_frame(stack: &mut Vec<Invocation>, roots: &mut Vec<Invocation>, outcome: Outcome) {
+    let Some(mut frame) = stack.pop() else {
+        return;
+    };
+    frame.outcome = outcome;
+
+    match stack.last_mut() {
+        Some(parent) => parent.children.push(frame),
+        None => roots.push(frame),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JUPITER: &str = "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4";
+    const TOKEN: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+    const COMPUTE_BUDGET: &str = "ComputeBudget111111111111111111111111111111";
+
+    fn lines(raw: &[&str]) -> Vec<String> {
+        raw.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_nested_invocations() {
+        let logs = lines(&[
+            &format!("Program {} invoke [1]", COMPUTE_BUDGET),
+            &format!("Program {} success", COMPUTE_BUDGET),
+            &format!("Program {} invoke [1]", JUPITER),
+            "Program log: Instruction: Route",
+            &format!("Program {} invoke [2]", TOKEN),
+            "Program log: Instruction: Transfer",
+            &format!("Program {} consumed 4645 of 180000 compute units", TOKEN),
+            &format!("Program {} success", TOKEN),
+            "Program log: back in parent",
+            &format!("Program {} invoke [2]", TOKEN),
+            "Program log: Error: insufficient funds",
+            &format!("Program {} consumed 3000 of 170000 compute units", TOKEN),
+            &format!("Program {} failed: custom program error: 0x1", TOKEN),
+            &format!("Program {} consumed 30000 of 200000 compute units", JUPITER),
+            &format!("Program {} failed: custom program error: 0x1", JUPITER),
+        ]);
+
+        let tree = build_invocation_tree(&logs);
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree[0].outcome, Outcome::Success);
+
+        let jupiter = &tree[1];
+        assert_eq!(jupiter.instruction_index, 1);
+        assert!(jupiter.is_failed());
+        assert_eq!(
+            jupiter.logs,
+            vec![
+                "Program log: Instruction: Route",
+                "Program log: back in parent"
+            ]
+        );
+        assert_eq!(jupiter.children.len(), 2);
+        assert_eq!(jupiter.children[0].outcome, Outcome::Success);
+        assert_eq!(jupiter.children[1].instruction_index, 1);
+        assert_eq!(jupiter.children_compute_used(), 7645);
+        assert_eq!(jupiter.self_compute_used(), Some(22355));
+
+        let leaf = jupiter.failing_leaf().unwrap();
+        assert_eq!(leaf.program_id, TOKEN);
+        assert_eq!(leaf.compute_used, Some(3000));
+    }
+
+    #[test]
+    fn test_consumed_after_failure() {
+        let logs = lines(&[
+            &format!("Program {} invoke [1]", JUPITER),
+            &format!("Program {} invoke [2]", TOKEN),
+            &format!("Program {} failed: custom program error: 0x1", TOKEN),
+            &format!("Program {} consumed 3000 of 170000 compute units", TOKEN),
+            &format!("Program {} failed: custom program error: 0x1", JUPITER),
+            &format!("Program {} consumed 30000 of 200000 compute units", JUPITER),
+        ]);
+
+        let tree = build_invocation_tree(&logs);
+        assert_eq!(tree[0].compute_used, Some(30000));
+        assert_eq!(tree[0].children[0].compute_used, Some(3000));
+        assert!(tree[0].logs.is_empty());
+    }
+
+    #[test]
+    fn test_truncated_logs() {
+        let logs = lines(&[
+            &format!("Program {} invoke [1]", JUPITER),
+            &format!("Program {} invoke [2]", TOKEN),
+            "Log truncated",
+        ]);
+
+        let tree = build_invocation_tree(&logs);
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].outcome, Outcome::Incomplete);
+        assert_eq!(tree[0].children[0].outcome, Outcome::Incomplete);
+    }
+}
@@ -0,0 +1,51 @@
+//! Decode Solana program errors and events.
+//!
+//! [`Decoder`] is the entry point: it resolves error codes and transaction
+//! logs against the built-in error registry, user error databases, local
+//! Anchor workspaces and (with the `rpc` feature) on-chain IDLs.
+//!
+//! ```no_run
+//! let decoder = ohsol::Decoder::new();
+//! let error = decoder.decode(6001, Some("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4"));
+//! assert_eq!(error.error_name.as_deref(), Some("SlippageToleranceExceeded"));
+//! ```
+//!
+//! Features:
+//! - `rpc` (default): fetch IDLs, transactions (one at a time or in batches)
+//!   and simulations over RPC, and watch programs over WebSocket.
+//! - `cli` (default): the `ohsol` binary.
+
+#[cfg(feature = "rpc")]
+pub mod batch;
+pub mod compute;
+pub mod config;
+pub mod decoded;
+pub mod decoder;
+pub mod error;
+pub mod events;
+pub mod idl;
+pub mod instruction;
+pub mod invocation;
+pub mod log_parser;
+pub mod network;
+pub mod return_data;
+#[cfg(feature = "rpc")]
+pub mod rpc;
+#[cfg(feature = "rpc")]
+pub mod scan;
+pub mod search;
+#[cfg(feature = "rpc")]
+pub mod simulate;
+pub mod transaction;
+#[cfg(feature = "rpc")]
+pub mod watch;
+pub mod workspace;
+
+pub use decoded::{Candidate, Confidence, DecodedError};
+pub use decoder::Decoder;
+pub use error::{OhsolError, Result};
+pub use events::DecodedEvent;
+pub use instruction::DecodedInstruction;
+pub use invocation::{Invocation, Outcome};
+pub use return_data::ReturnData;
+pub use transaction::TransactionReport;
@@ -1,47 +1,172 @@
 mod cli;
-mod decoder;
-mod idl;
-mod log_parser;
 mod output;
-mod rpc;
 
-use clap::Parser;
-use cli::{Cli, Commands};
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
+use cli::{CacheCommand, Cli, Commands};
+use ohsol::network::{self, Endpoint, EndpointOverrides};
+use ohsol::{batch, config, decoder, idl, rpc, scan, simulate, watch, workspace, Decoder, OhsolError};
 use std::io::{self, BufRead};
+use std::path::Path;
+use std::process::ExitCode;
 
-fn main() -> anyhow::Result<()> {
+fn main() -> ExitCode {
     let cli = Cli::parse();
 
+    match run(&cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            // Library failures carry a category; anything else is a generic failure.
+            let code = match err.downcast_ref::<OhsolError>() {
+                Some(err) => err.exit_code(),
+                None => 1,
+            };
+            output::format_failure(&err, cli.json);
+            ExitCode::from(code)
+        }
+    }
+}
+
+fn run(cli: &Cli) -> anyhow::Result<()> {
+    let needs_rpc = cli.command.needs_rpc();
+    let config = match config::load() {
+        Ok(config) => config,
+        Err(e) if !needs_rpc => {
+            eprintln!("Warning: ignoring config: {}", e);
+            config::Config::default()
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let endpoint = match resolve_endpoint(cli, &config) {
+        Ok(endpoint) => Some(endpoint),
+        Err(e) if !needs_rpc => {
+            eprintln!("Warning: {}; working offline", e);
+            None
+        }
+        Err(e) => return Err(e),
+    };
+    if let Some(endpoint) = &endpoint {
+        rpc::configure(endpoint)?;
+    }
+    let endpoint = endpoint.as_ref();
+    let decoder = build_decoder(cli, &config, endpoint)?;
+
     match &cli.command {
         Commands::Decode { code, program, stdin } => {
             if *stdin {
-                handle_decode_stdin(&cli, program.as_deref())?;
+                handle_decode_stdin(cli, &decoder, program.as_deref())?;
             } else {
-                handle_decode(&cli, code, program.as_deref())?;
+                handle_decode(cli, &decoder, code, program.as_deref())?;
             }
         }
-        Commands::Tx { signature } => {
-            handle_transaction(&cli, signature)?;
+        Commands::Tx { signatures, file, from_file, concurrency, tree, compute } => {
+            if from_file.is_some() || signatures.len() > 1 {
+                // clap can't make a flag conflict with a second value.
+                if *tree || *compute {
+                    let flag = if *tree { "--tree" } else { "--compute" };
+                    Cli::command()
+                        .error(ErrorKind::ArgumentConflict, format!("{} takes a single signature, not a batch", flag))
+                        .exit();
+                }
+                handle_batch(cli, &decoder, signatures, from_file.as_deref(), *concurrency)?;
+            } else {
+                let signature = signatures.first().map(String::as_str);
+                handle_transaction(cli, &decoder, signature, file.as_deref(), *tree, *compute)?;
+            }
+        }
+        Commands::Idl { program_id, output, refresh } => {
+            handle_idl(endpoint.ok_or(OhsolError::NoRpcUrl)?, program_id, output.as_ref(), *refresh)?;
+        }
+        Commands::Simulate { transaction, keep_blockhash, tree } => {
+            handle_simulate(cli, &decoder, transaction, *keep_blockhash, *tree)?;
         }
-        Commands::Idl { program_id, output } => {
-            handle_idl(&cli, program_id, output.as_ref())?;
+        Commands::Watch { program_id, all, limit } => {
+            handle_watch(cli, &decoder, endpoint.ok_or(OhsolError::NoRpcUrl)?, program_id, *all, *limit)?;
+        }
+        Commands::Scan { address, limit, before, until, concurrency } => {
+            let options = scan::ScanOptions {
+                limit: *limit,
+                before: before.clone(),
+                until: until.clone(),
+                concurrency: *concurrency,
+            };
+            handle_scan(cli, &decoder, address, &options)?;
+        }
+        Commands::Cache { action } => {
+            handle_cache(cli, endpoint, action)?;
         }
         Commands::List { program } => {
-            handle_list(&cli, program)?;
+            handle_list(cli, program)?;
+        }
+        Commands::Search { query } => {
+            handle_search(cli, &decoder, &query.join(" "))?;
         }
     }
 
     Ok(())
 }
 
-fn handle_decode(cli: &Cli, code_str: &str, program_id: Option<&str>) -> anyhow::Result<()> {
+/// Pick the RPC endpoint from the flags, the environment, the config file's
+/// profiles and the Solana CLI config.
+fn resolve_endpoint(cli: &Cli, config: &config::Config) -> anyhow::Result<Endpoint> {
+    let overrides = EndpointOverrides {
+        rpc_url: cli.rpc_url.clone(),
+        ws_url: cli.ws_url.clone(),
+        cluster: cli.cluster,
+        profile: cli.profile.clone(),
+        env_rpc_url: std::env::var("SOLANA_RPC_URL").ok().filter(|url| !url.is_empty()),
+    };
+    let solana = network::solana_cli_config_path()
+        .map(|path| network::load_solana_cli_config(&path))
+        .unwrap_or_default();
+    Ok(network::resolve_endpoint(&overrides, config, &solana)?)
+}
+
+/// Set up the decoder for the configured RPC URL and merge local error sources
+/// over the built-in registry: the Anchor workspace's IDLs, then config
+/// entries, then the profile's, then `--errors-db` flags, so the command line
+/// has the last word.
+fn build_decoder(cli: &Cli, config: &config::Config, endpoint: Option<&Endpoint>) -> anyhow::Result<Decoder> {
+    // The output names programs through the process-wide registry, so the
+    // sources go into that one.
+    let mut decoder = Decoder::shared();
+    if let Some(endpoint) = endpoint {
+        decoder = decoder.with_rpc_url(endpoint.rpc_url.clone());
+    }
+
+    match &cli.workspace {
+        Some(root) => {
+            decoder.load_workspace(root)?;
+        }
+        None => {
+            let detected = std::env::current_dir()
+                .ok()
+                .and_then(|cwd| workspace::find_workspace(&cwd));
+            if let Some(root) = detected {
+                // A broken workspace we merely happened to be in shouldn't
+                // stop unrelated commands.
+                if let Err(e) = decoder.load_workspace(&root) {
+                    eprintln!("Warning: ignoring Anchor workspace {}: {}", root.display(), e);
+                }
+            }
+        }
+    }
+
+    let profile_errors_db = endpoint.map_or(&[][..], |endpoint| &endpoint.errors_db);
+    for path in config.errors_db.iter().chain(profile_errors_db).chain(&cli.errors_db) {
+        decoder.load_errors_db(path)?;
+    }
+    Ok(decoder)
+}
+
+fn handle_decode(cli: &Cli, decoder: &Decoder, code_str: &str, program_id: Option<&str>) -> anyhow::Result<()> {
     let code = decoder::anchor::parse_error_code(code_str)?;
-    let error = decoder::decode_error(code, program_id);
+    let error = decoder.decode(code, program_id);
     output::format_error(&error, cli.json);
     Ok(())
 }
 
-fn handle_decode_stdin(cli: &Cli, program_id: Option<&str>) -> anyhow::Result<()> {
+fn handle_decode_stdin(cli: &Cli, decoder: &Decoder, program_id: Option<&str>) -> anyhow::Result<()> {
     let stdin = io::stdin();
     for line in stdin.lock().lines() {
         let line = line?;
@@ -53,11 +178,11 @@ fn handle_decode_stdin(cli: &Cli, program_id: Option<&str>) -> anyhow::Result<()
         // Try to extract error codes from the line
         if let Some(hex_code) = extract_hex_error(&line) {
             if let Ok(code) = u32::from_str_radix(&hex_code, 16) {
-                let error = decoder::decode_error(code, program_id);
+                let error = decoder.decode(code, program_id);
                 output::format_error(&error, cli.json);
             }
         } else if let Ok(code) = decoder::anchor::parse_error_code(trimmed) {
-            let error = decoder::decode_error(code, program_id);
+            let error = decoder.decode(code, program_id);
             output::format_error(&error, cli.json);
         }
     }
@@ -79,17 +204,84 @@ fn extract_hex_error(line: &str) -> Option<String> {
     None
 }
 
-fn handle_transaction(cli: &Cli, signature: &str) -> anyhow::Result<()> {
-    let rpc_url = rpc::get_rpc_url(cli.rpc_url.clone());
-    let errors = rpc::fetch_transaction_errors(&rpc_url, signature)?;
+fn handle_transaction(
+    cli: &Cli,
+    decoder: &Decoder,
+    signature: Option<&str>,
+    file: Option<&Path>,
+    tree: bool,
+    compute: bool,
+) -> anyhow::Result<()> {
+    let report = match (signature, file) {
+        (Some(signature), _) => decoder.explain_transaction(signature)?,
+        (None, Some(path)) => {
+            let json = if path == Path::new("-") {
+                io::read_to_string(io::stdin())?
+            } else {
+                std::fs::read_to_string(path)
+                    .map_err(|e| OhsolError::InvalidTransaction(format!("{}: {}", path.display(), e)))?
+            };
+            decoder.explain_transaction_json(&json)?
+        }
+        (None, None) => unreachable!("clap requires a signature or --file"),
+    };
+
+    // Only the full report carries the flag, and the text report below only
+    // prints when there are errors; otherwise say it on stderr.
+    if report.logs_truncated && !tree && !compute && (cli.json || report.errors.is_empty()) {
+        eprintln!("Warning: the logs were truncated by the runtime; the decoded errors may be incomplete.");
+    }
+
+    if report.errors.is_empty() && !tree && !compute {
+        return Err(OhsolError::NoErrors.into());
+    }
 
     if cli.json {
-        println!("{}", serde_json::to_string_pretty(&errors)?);
+        if tree || compute {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            println!("{}", serde_json::to_string_pretty(&report.errors)?);
+        }
     } else {
-        println!("Transaction: {}", signature);
-        println!("Found {} error(s):\n", errors.len());
+        // Saved logs carry no signature.
+        if !report.signature.is_empty() {
+            println!("Transaction: {}", report.signature);
+        }
+
+        if report.logs_truncated {
+            println!();
+            output::format_truncation_warning();
+        }
+
+        if tree {
+            println!();
+            output::format_invocation_tree(&report.invocations);
+        }
+
+        if compute {
+            println!();
+            output::format_compute(&report);
+        }
+
+        if let Some(return_data) = &report.return_data {
+            println!();
+            output::format_return_data(return_data);
+        }
 
-        for (i, error) in errors.iter().enumerate() {
+        if let Some(instruction) = &report.failed_instruction {
+            println!();
+            output::format_instruction(instruction);
+        }
+
+        // Events often show the state that led to the failure.
+        if !report.events.is_empty() {
+            println!();
+            output::format_events(&report.events);
+        }
+
+        println!("\nFound {} error(s):\n", report.errors.len());
+
+        for (i, error) in report.errors.iter().enumerate() {
             if i > 0 {
                 println!();
             }
@@ -100,15 +292,147 @@ fn handle_transaction(cli: &Cli, signature: &str) -> anyhow::Result<()> {
     Ok(())
 }
 
-fn handle_idl(cli: &Cli, program_id_str: &str, output_path: Option<&std::path::PathBuf>) -> anyhow::Result<()> {
+fn handle_batch(
+    cli: &Cli,
+    decoder: &Decoder,
+    signatures: &[String],
+    from_file: Option<&Path>,
+    concurrency: usize,
+) -> anyhow::Result<()> {
+    let mut list = signatures.join("\n");
+    if let Some(path) = from_file {
+        let text = if path == Path::new("-") {
+            io::read_to_string(io::stdin())?
+        } else {
+            std::fs::read_to_string(path)
+                .map_err(|e| OhsolError::InvalidSignature(format!("{}: {}", path.display(), e)))?
+        };
+        list.push('\n');
+        list.push_str(&text);
+    }
+    let signatures = batch::parse_signature_list(&list);
+    if signatures.is_empty() {
+        return Err(OhsolError::InvalidSignature("no signatures given".to_string()).into());
+    }
+
+    if !cli.json {
+        eprintln!("Analyzing {} transaction(s)...\n", signatures.len());
+    }
+    let report = batch::fetch_transaction_reports(decoder, &signatures, concurrency)?;
+
+    if cli.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        output::format_batch(&report);
+    }
+    Ok(())
+}
+
+fn handle_simulate(
+    cli: &Cli,
+    decoder: &Decoder,
+    input: &str,
+    keep_blockhash: bool,
+    tree: bool,
+) -> anyhow::Result<()> {
+    let transaction = simulate::read_transaction(input)?;
+    let report = simulate::simulate_transaction(decoder, &transaction, !keep_blockhash)?;
+
+    if cli.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    output::format_simulation(&report, tree);
+    Ok(())
+}
+
+fn handle_watch(
+    cli: &Cli,
+    decoder: &Decoder,
+    endpoint: &Endpoint,
+    program_id: &str,
+    all: bool,
+    limit: Option<usize>,
+) -> anyhow::Result<()> {
+    decoder::parse_program_id(program_id)?;
+    let ws_url = endpoint
+        .ws_url
+        .clone()
+        .unwrap_or_else(|| watch::ws_url_from_rpc(&endpoint.rpc_url));
+
+    if !cli.json {
+        eprintln!("Watching {} via {} (Ctrl-C to stop)\n", program_id, ws_url);
+    }
+
+    let mut shown = 0;
+    // The callback can only stop the stream, so a failure is kept for after it.
+    let mut failure = None;
+    watch::watch_program(&ws_url, decoder, program_id, |tx| {
+        if !tx.failed && !all {
+            return true;
+        }
+
+        if cli.json {
+            // One object per line so the stream can be piped into jq.
+            match serde_json::to_string(tx) {
+                Ok(line) => println!("{}", line),
+                Err(e) => {
+                    failure = Some(e);
+                    return false;
+                }
+            }
+        } else {
+            output::format_watched_transaction(tx);
+        }
+
+        shown += 1;
+        limit.is_none_or(|limit| shown < limit)
+    })?;
+
+    match failure {
+        Some(e) => Err(e.into()),
+        None => Ok(()),
+    }
+}
+
+fn handle_scan(cli: &Cli, decoder: &Decoder, address: &str, options: &scan::ScanOptions) -> anyhow::Result<()> {
+    if !cli.json {
+        eprintln!("Scanning up to {} recent transaction(s) of {}...\n", options.limit, address);
+    }
+    let report = scan::scan_address(decoder, address, options)?;
+
+    if cli.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        output::format_scan(&report);
+    }
+    Ok(())
+}
+
+fn handle_idl(
+    endpoint: &Endpoint,
+    program_id_str: &str,
+    output_path: Option<&std::path::PathBuf>,
+    refresh: bool,
+) -> anyhow::Result<()> {
     let program_id = decoder::parse_program_id(program_id_str)?;
-    let rpc_url = rpc::get_rpc_url(cli.rpc_url.clone());
+    let rpc_url = &endpoint.rpc_url;
+    let cache = idl::cache::IdlCache::open();
 
-    println!("Fetching IDL for program: {}", program_id);
+    let entry = match cache.get(&idl::cache::network_name(rpc_url), program_id_str) {
+        Some(entry) if !refresh => entry,
+        _ => {
+            eprintln!("Fetching IDL for program: {}", program_id);
+            idl::cache::refresh(&cache, rpc_url, &program_id)?
+        }
+    };
 
-    let idl = idl::fetch_idl_with_url(&rpc_url, &program_id)?;
+    let idl = entry
+        .parse_idl()
+        .ok_or_else(|| OhsolError::IdlNotFound(program_id.to_string()))??;
 
-    let json = serde_json::to_string_pretty(&idl)?;
+    let json = serde_json::to_string_pretty(&entry.idl)?;
 
     if let Some(path) = output_path {
         std::fs::write(path, &json)?;
@@ -120,6 +444,69 @@ fn handle_idl(cli: &Cli, program_id_str: &str, output_path: Option<&std::path::P
     println!("\nProgram: {} v{}", idl.get_name(), idl.get_version());
     println!("Format: {}", if idl.is_modern_format() { "Modern" } else { "Legacy" });
     println!("Errors: {}", idl.errors.len());
+    println!("Fetched at slot {} on {}", entry.slot, entry.network);
+
+    Ok(())
+}
+
+fn handle_cache(cli: &Cli, endpoint: Option<&Endpoint>, action: &CacheCommand) -> anyhow::Result<()> {
+    let cache = idl::cache::IdlCache::open();
+
+    match action {
+        CacheCommand::List => {
+            let entries = cache.list()?;
+            if cli.json {
+                println!("{}", serde_json::to_string_pretty(&output::cache_summaries(&entries))?);
+            } else if entries.is_empty() {
+                println!("IDL cache is empty ({})", cache.dir().display());
+            } else {
+                println!("Cached IDLs in {}:\n", cache.dir().display());
+                output::format_cache_entries(&entries);
+            }
+        }
+        CacheCommand::Clear { program_id } => {
+            let removed = cache.clear(program_id.as_deref())?;
+            println!("Removed {} cached IDL(s)", removed);
+        }
+        CacheCommand::Refresh { program_id } => {
+            let rpc_url = &endpoint.ok_or(OhsolError::NoRpcUrl)?.rpc_url;
+            let network = idl::cache::network_name(rpc_url);
+
+            let program_ids: Vec<String> = match program_id {
+                Some(pid) => vec![pid.clone()],
+                None => cache
+                    .list()?
+                    .into_iter()
+                    .filter(|e| e.network == network)
+                    .map(|e| e.program_id)
+                    .collect(),
+            };
+
+            for pid in &program_ids {
+                let program_id = decoder::parse_program_id(pid)?;
+                let entry = idl::cache::refresh(&cache, rpc_url, &program_id)?;
+                let status = if entry.idl.is_some() { "IDL found" } else { "no IDL" };
+                println!("Refreshed {} on {} at slot {} ({})", pid, network, entry.slot, status);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_search(cli: &Cli, decoder: &Decoder, query: &str) -> anyhow::Result<()> {
+    let matches = decoder.search(query);
+
+    if matches.is_empty() {
+        return Err(OhsolError::NoMatches(query.to_string()).into());
+    }
+
+    if cli.json {
+        println!("{}", serde_json::to_string_pretty(&matches)?);
+    } else {
+        println!("Found {} error(s) matching '{}':\n", matches.len(), query);
+        output::format_search_results(&matches);
+    }
 
     Ok(())
 }
@@ -138,9 +525,6 @@ fn handle_list(cli: &Cli, program: &str) -> anyhow::Result<()> {
         }
         Ok(())
     } else {
-        Err(anyhow::anyhow!(
-            "Unknown program: {}. Try using a program ID or known name like 'spl-token' or 'jupiter'",
-            program
-        ))
+        Err(OhsolError::UnknownProgram(program.to_string()).into())
     }
 }
@@ -0,0 +1,322 @@
+use crate::config::Config;
+use crate::{OhsolError, Result};
+use serde::Serialize;
+use solana_sdk::commitment_config::CommitmentLevel;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Public clusters, with the monikers the Solana CLI accepts for `--url`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cluster {
+    Mainnet,
+    Devnet,
+    Testnet,
+    Localnet,
+}
+
+impl Cluster {
+    pub fn rpc_url(self) -> &'static str {
+        match self {
+            Cluster::Mainnet => "https://api.mainnet-beta.solana.com",
+            Cluster::Devnet => "https://api.devnet.solana.com",
+            Cluster::Testnet => "https://api.testnet.solana.com",
+            Cluster::Localnet => "http://127.0.0.1:8899",
+        }
+    }
+}
+
+impl FromStr for Cluster {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "m" | "mainnet" | "mainnet-beta" => Ok(Cluster::Mainnet),
+            "d" | "devnet" => Ok(Cluster::Devnet),
+            "t" | "testnet" => Ok(Cluster::Testnet),
+            "l" | "localnet" | "localhost" => Ok(Cluster::Localnet),
+            _ => Err(format!(
+                "unknown cluster '{}' (expected mainnet, devnet, testnet or localnet)",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Cluster {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Cluster::Mainnet => "mainnet",
+            Cluster::Devnet => "devnet",
+            Cluster::Testnet => "testnet",
+            Cluster::Localnet => "localnet",
+        })
+    }
+}
+
+/// A URL, or a cluster moniker standing for its public RPC URL.
+pub fn url_or_moniker(value: &str) -> String {
+    match value.parse::<Cluster>() {
+        Ok(cluster) => cluster.rpc_url().to_string(),
+        Err(_) => value.to_string(),
+    }
+}
+
+/// The settings ohsol shares with the Solana CLI's `config.yml`.
+#[derive(Debug, Default, PartialEq)]
+pub struct SolanaCliConfig {
+    pub json_rpc_url: Option<String>,
+    pub websocket_url: Option<String>,
+    pub commitment: Option<CommitmentLevel>,
+}
+
+/// `~/.config/solana/cli/config.yml`, where `solana config set` writes.
+pub fn solana_cli_config_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config/solana/cli/config.yml"))
+}
+
+/// Read the Solana CLI config. A missing or unreadable file is an empty one,
+/// since it's only ever a fallback.
+pub fn load_solana_cli_config(path: &Path) -> SolanaCliConfig {
+    std::fs::read_to_string(path)
+        .map(|data| parse_solana_cli_config(&data))
+        .unwrap_or_default()
+}
+
+/// Pick the top-level scalars out of the YAML the Solana CLI writes. Empty
+/// values mean "unset" there (`websocket_url: ""` derives it from the RPC URL).
+pub fn parse_solana_cli_config(data: &str) -> SolanaCliConfig {
+    let mut config = SolanaCliConfig::default();
+
+    for line in data.lines() {
+        if line.starts_with([' ', '\t', '#']) {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+        if value.is_empty() {
+            continue;
+        }
+        match key.trim() {
+            "json_rpc_url" => config.json_rpc_url = Some(value.to_string()),
+            "websocket_url" => config.websocket_url = Some(value.to_string()),
+            "commitment" => config.commitment = value.parse().ok(),
+            _ => {}
+        }
+    }
+
+    config
+}
+
+/// Where requests go and how: the result of combining flags, the environment,
+/// ohsol profiles and the Solana CLI config.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Endpoint {
+    pub rpc_url: String,
+    /// PubSub URL, when configured rather than derived from `rpc_url`.
+    pub ws_url: Option<String>,
+    /// Extra HTTP headers sent with every RPC request, e.g. an API key.
+    pub headers: BTreeMap<String, String>,
+    pub commitment: CommitmentLevel,
+    /// The profile the endpoint came from, if any.
+    pub profile: Option<String>,
+    /// Profile error databases, loaded after the config file's own.
+    #[serde(skip)]
+    pub errors_db: Vec<PathBuf>,
+}
+
+/// Endpoint choices made on the command line.
+#[derive(Debug, Default)]
+pub struct EndpointOverrides {
+    pub rpc_url: Option<String>,
+    pub ws_url: Option<String>,
+    pub cluster: Option<Cluster>,
+    pub profile: Option<String>,
+    /// `$SOLANA_RPC_URL`, which ranks below the flags.
+    pub env_rpc_url: Option<String>,
+}
+
+/// Resolve the endpoint, first match wins for the RPC URL:
+///
+/// 1. `--rpc-url`, then `--cluster`
+/// 2. `$SOLANA_RPC_URL`, unless a profile was asked for by name
+/// 3. the `rpc_url` of the `--profile`, else of the config's `default_profile`
+/// 4. `json_rpc_url` from the Solana CLI config
+/// 5. mainnet
+///
+/// The selected profile still supplies commitment and error databases when
+/// another level picks the URL, with the Solana CLI config's commitment as the
+/// fallback. WebSocket URLs and headers only stick with the RPC URL they came
+/// with, so API keys are never sent to another host.
+pub fn resolve_endpoint(
+    overrides: &EndpointOverrides,
+    config: &Config,
+    solana: &SolanaCliConfig,
+) -> Result<Endpoint> {
+    let profile_name = overrides
+        .profile
+        .as_ref()
+        .or(config.default_profile.as_ref());
+    let profile = profile_name
+        .map(|name| {
+            config
+                .profiles
+                .get(name)
+                .ok_or_else(|| OhsolError::UnknownProfile(name.clone()))
+        })
+        .transpose()?;
+
+    let mut endpoint = Endpoint {
+        rpc_url: solana
+            .json_rpc_url
+            .clone()
+            .unwrap_or_else(|| Cluster::Mainnet.rpc_url().to_string()),
+        ws_url: solana.websocket_url.clone(),
+        headers: BTreeMap::new(),
+        commitment: solana.commitment.unwrap_or(CommitmentLevel::Confirmed),
+        profile: None,
+        errors_db: Vec::new(),
+    };
+
+    if let Some(profile) = profile {
+        if let Some(rpc_url) = &profile.rpc_url {
+            endpoint.rpc_url = url_or_moniker(rpc_url);
+            endpoint.ws_url = profile.ws_url.clone();
+            endpoint.headers = profile.headers.clone();
+        }
+        if let Some(commitment) = profile.commitment {
+            endpoint.commitment = commitment;
+        }
+        endpoint.profile = profile_name.cloned();
+        endpoint.errors_db = profile.errors_db.clone();
+    }
+
+    // An explicit --profile outranks the environment; a default one doesn't.
+    let env_url = overrides
+        .env_rpc_url
+        .clone()
+        .filter(|_| overrides.profile.is_none());
+    let url = overrides
+        .rpc_url
+        .as_deref()
+        .map(url_or_moniker)
+        .or_else(|| overrides.cluster.map(|c| c.rpc_url().to_string()))
+        .or(env_url);
+    if let Some(url) = url {
+        endpoint.rpc_url = url;
+        endpoint.ws_url = None;
+        endpoint.headers.clear();
+    }
+    if overrides.ws_url.is_some() {
+        endpoint.ws_url = overrides.ws_url.clone();
+    }
+
+    Ok(endpoint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Profile;
+
+    #[test]
+    fn test_parse_solana_cli_config() {
+        let yaml = "---\n\
+            json_rpc_url: \"https://api.devnet.solana.com\"\n\
+            websocket_url: \"\"\n\
+            keypair_path: /home/sol/.config/solana/id.json\n\
+            address_labels:\n  \"11111111111111111111111111111111\": System Program\n\
+            commitment: finalized\n";
+        assert_eq!(
+            parse_solana_cli_config(yaml),
+            SolanaCliConfig {
+                json_rpc_url: Some("https://api.devnet.solana.com".to_string()),
+                websocket_url: None,
+                commitment: Some(CommitmentLevel::Finalized),
+            }
+        );
+    }
+
+    #[test]
+    fn test_resolve_endpoint_precedence() {
+        let mut config = Config::default();
+        config.profiles.insert(
+            "helius".to_string(),
+            Profile {
+                rpc_url: Some("https://mainnet.helius-rpc.com".to_string()),
+                ws_url: Some("wss://mainnet.helius-rpc.com".to_string()),
+                headers: BTreeMap::from([("x-api-key".to_string(), "secret".to_string())]),
+                commitment: Some(CommitmentLevel::Processed),
+                errors_db: Vec::new(),
+            },
+        );
+        config.profiles.insert(
+            "finalized".to_string(),
+            Profile {
+                commitment: Some(CommitmentLevel::Finalized),
+                ..Profile::default()
+            },
+        );
+        let solana = SolanaCliConfig {
+            json_rpc_url: Some("http://127.0.0.1:8899".to_string()),
+            ..SolanaCliConfig::default()
+        };
+        let resolve =
+            |overrides: EndpointOverrides| resolve_endpoint(&overrides, &config, &solana).unwrap();
+
+        let endpoint = resolve(EndpointOverrides::default());
+        assert_eq!(endpoint.rpc_url, "http://127.0.0.1:8899");
+        assert_eq!(endpoint.commitment, CommitmentLevel::Confirmed);
+
+        let endpoint = resolve(EndpointOverrides {
+            profile: Some("helius".to_string()),
+            ..EndpointOverrides::default()
+        });
+        assert_eq!(
+            endpoint.ws_url.as_deref(),
+            Some("wss://mainnet.helius-rpc.com")
+        );
+        assert_eq!(endpoint.commitment, CommitmentLevel::Processed);
+
+        // A cluster replaces the profile's URLs and headers but keeps its other settings.
+        let endpoint = resolve(EndpointOverrides {
+            profile: Some("helius".to_string()),
+            cluster: Some(Cluster::Devnet),
+            ..EndpointOverrides::default()
+        });
+        assert_eq!(endpoint.rpc_url, "https://api.devnet.solana.com");
+        assert_eq!(endpoint.ws_url, None);
+        assert!(endpoint.headers.is_empty());
+        assert_eq!(endpoint.commitment, CommitmentLevel::Processed);
+
+        // A profile without a URL falls through to the Solana CLI config.
+        let endpoint = resolve(EndpointOverrides {
+            profile: Some("finalized".to_string()),
+            ..EndpointOverrides::default()
+        });
+        assert_eq!(endpoint.rpc_url, "http://127.0.0.1:8899");
+        assert_eq!(endpoint.commitment, CommitmentLevel::Finalized);
+
+        let endpoint = resolve(EndpointOverrides {
+            rpc_url: Some("t".to_string()),
+            cluster: Some(Cluster::Devnet),
+            ..EndpointOverrides::default()
+        });
+        assert_eq!(endpoint.rpc_url, "https://api.testnet.solana.com");
+
+        assert!(matches!(
+            resolve_endpoint(
+                &EndpointOverrides {
+                    profile: Some("missing".to_string()),
+                    ..EndpointOverrides::default()
+                },
+                &config,
+                &solana
+            ),
+            Err(OhsolError::UnknownProfile(_))
+        ));
+    }
+}
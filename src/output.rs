@@ -1,56 +1,34 @@
 use colored::Colorize;
+use ohsol::batch::{BatchReport, Tally};
+use ohsol::scan::ScanReport;
+use ohsol::idl::cache::CacheEntry;
+use ohsol::events::DecodedEvent;
+use ohsol::instruction::DecodedInstruction;
+use ohsol::return_data::ReturnData;
+use ohsol::search::ErrorMatch;
+use ohsol::transaction::TransactionReport;
+use ohsol::invocation::{Invocation, Outcome};
+use ohsol::simulate::SimulationReport;
+use ohsol::watch::WatchedTransaction;
+use ohsol::error::ErrorReport;
+use ohsol::{Confidence, DecodedError, OhsolError};
 use serde::Serialize;
 
-#[derive(Serialize, Debug, Clone)]
-pub struct DecodedError {
-    pub program_id: String,
-    pub error_code: u32,
-    pub error_hex: String,
-    pub error_name: Option<String>,
-    pub error_message: Option<String>,
-    pub source_file: Option<String>,
-    pub source_line: Option<u32>,
-    pub cpi_depth: Option<u8>,
-}
-
-impl DecodedError {
-    pub fn new(code: u32) -> Self {
-        Self {
-            program_id: String::new(),
-            error_code: code,
-            error_hex: format!("0x{:x}", code),
-            error_name: None,
-            error_message: None,
-            source_file: None,
-            source_line: None,
-            cpi_depth: None,
-        }
-    }
-
-    pub fn with_program(mut self, program_id: String) -> Self {
-        self.program_id = program_id;
-        self
-    }
-
-    pub fn with_name(mut self, name: String) -> Self {
-        self.error_name = Some(name);
-        self
-    }
-
-    pub fn with_message(mut self, message: String) -> Self {
-        self.error_message = Some(message);
-        self
-    }
-
-    pub fn with_source(mut self, file: String, line: u32) -> Self {
-        self.source_file = Some(file);
-        self.source_line = Some(line);
-        self
-    }
-
-    pub fn with_cpi_depth(mut self, depth: u8) -> Self {
-        self.cpi_depth = Some(depth);
-        self
+/// Report a failed command: a JSON error object on stdout in `--json` mode,
+/// otherwise a message on stderr.
+pub fn format_failure(err: &anyhow::Error, json_mode: bool) {
+    if json_mode {
+        let report = match err.downcast_ref::<OhsolError>() {
+            Some(err) => ErrorReport::from(err),
+            None => ErrorReport {
+                kind: "error",
+                message: format!("{:#}", err),
+                exit_code: 1,
+            },
+        };
+        println!("{}", serde_json::json!({ "error": report }));
+    } else {
+        eprintln!("{} {:#}", "Error:".red().bold(), err);
     }
 }
 
@@ -77,15 +55,59 @@ pub fn format_error(error: &DecodedError, json_mode: bool) {
             println!("  {} {}", "Message:".bold(), msg);
         }
 
+        if let Some(account) = &error.account_name {
+            println!("  {} {}", "Account:".bold(), account.cyan());
+        }
+
+        if let Some(left) = &error.left_value {
+            println!("  {} {}", "Left:".bold(), left);
+        }
+
+        if let Some(right) = &error.right_value {
+            println!("  {} {}", "Right:".bold(), right);
+        }
+
+        if let Some(explanation) = &error.explanation {
+            println!("  {} {}", "Explanation:".bold(), explanation);
+        }
+
+        if !error.likely_causes.is_empty() {
+            println!("  {}", "Likely causes:".bold());
+            for cause in &error.likely_causes {
+                println!("    - {}", cause);
+            }
+        }
+
         if let Some(file) = &error.source_file {
             if let Some(line) = error.source_line {
                 println!("  {} {}:{}", "Source:".bold(), file.dimmed(), line);
             }
         }
 
+        if let Some(index) = error.instruction_index {
+            println!("  {} {}", "Instruction:".bold(), index);
+        }
+
         if let Some(depth) = error.cpi_depth {
             println!("  {} {}", "CPI Depth:".bold(), depth);
         }
+
+        if !error.alternatives.is_empty() {
+            println!("  {}", "Could also be:".bold());
+            for candidate in &error.alternatives {
+                let confidence = match candidate.confidence {
+                    Confidence::High => "high".green(),
+                    Confidence::Medium => "medium".yellow(),
+                    Confidence::Low => "low".dimmed(),
+                };
+                println!(
+                    "    - {} {} [{}]",
+                    candidate.error.error_name.as_deref().unwrap_or("Unknown").cyan(),
+                    format!("({})", candidate.reason).dimmed(),
+                    confidence
+                );
+            }
+        }
     }
 }
 
@@ -111,3 +133,517 @@ pub fn format_error_list(errors: &[DecodedError], json_mode: bool) {
         println!("{}", table);
     }
 }
+
+/// Print search hits as a table, best match first.
+pub fn format_search_results(matches: &[ErrorMatch]) {
+    use comfy_table::{Table, presets::UTF8_FULL};
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(vec!["Program", "Code", "Hex", "Name", "Message"]);
+
+    for m in matches {
+        let program = match &m.program_name {
+            Some(name) => format!("{}\n{}", name, m.program_id),
+            None => m.program_id.clone(),
+        };
+        table.add_row(vec![
+            program,
+            m.error_code.to_string(),
+            m.error_hex.clone(),
+            m.error_name.clone(),
+            if m.error_message.is_empty() { "-".to_string() } else { m.error_message.clone() },
+        ]);
+    }
+
+    println!("{}", table);
+}
+
+pub fn format_simulation(report: &SimulationReport, tree: bool) {
+    if report.failed {
+        println!("{}", "Simulation failed".red().bold());
+    } else {
+        println!("{}", "Simulation succeeded".green().bold());
+    }
+
+    if let Some(units) = report.units_consumed {
+        println!("  {} {}", "Compute units:".bold(), units);
+    }
+
+    if report.logs_truncated {
+        println!();
+        format_truncation_warning();
+    }
+
+    if let Some(return_data) = &report.return_data {
+        println!();
+        format_return_data(return_data);
+    }
+
+    if tree {
+        println!();
+        format_invocation_tree(&report.invocations);
+    }
+
+    if let Some(instruction) = &report.failed_instruction {
+        println!();
+        format_instruction(instruction);
+    }
+
+    if !report.events.is_empty() {
+        println!();
+        format_events(&report.events);
+    }
+
+    if !report.errors.is_empty() {
+        println!("\nFound {} error(s):\n", report.errors.len());
+        for (i, error) in report.errors.iter().enumerate() {
+            if i > 0 {
+                println!();
+            }
+            format_error(error, false);
+        }
+    }
+}
+
+/// Warn that the runtime stopped recording logs partway, so errors logged
+/// after the cut-off were never seen.
+pub fn format_truncation_warning() {
+    println!(
+        "{}",
+        "Warning: the logs were truncated by the runtime; the decoded errors may be incomplete.".yellow()
+    );
+}
+
+/// Print return data, decoded with the returning instruction's IDL type when
+/// one applied.
+pub fn format_return_data(return_data: &ReturnData) {
+    let program = ohsol::decoder::registry::get_program_name(&return_data.program_id)
+        .unwrap_or_else(|| return_data.program_id.clone());
+    let source = match &return_data.instruction {
+        Some(name) => format!("{} {}", name.cyan(), format!("on {}", program).dimmed()),
+        None => format!("from {}", program).dimmed().to_string(),
+    };
+    println!(
+        "{} {} {}",
+        "Return data:".bold(),
+        source,
+        format!("({} bytes)", return_data.data_len).dimmed()
+    );
+
+    print_fields(return_data.value.as_ref(), return_data.decode_error.as_deref(), &return_data.data_base64);
+}
+
+/// Print events in the order they were logged, one field per line.
+pub fn format_events(events: &[DecodedEvent]) {
+    println!("{} ({}):", "Events".bold(), events.len());
+
+    for event in events {
+        let program = ohsol::decoder::registry::get_program_name(&event.program_id)
+            .unwrap_or_else(|| event.program_id.clone());
+        let name = match &event.name {
+            Some(name) => name.cyan().to_string(),
+            None => "Unknown event".dimmed().to_string(),
+        };
+        println!(
+            "  {} {} {}",
+            format!("[ix {}]", event.instruction_index).dimmed(),
+            name,
+            format!("from {}", program).dimmed()
+        );
+
+        print_fields(event.fields.as_ref(), event.decode_error.as_deref(), &event.data_base64);
+    }
+}
+
+/// Print the failing instruction: its name, arguments and accounts. Accounts
+/// whose signer/writable flags differ from what the IDL expects are marked.
+pub fn format_instruction(instruction: &DecodedInstruction) {
+    let program = ohsol::decoder::registry::get_program_name(&instruction.program_id)
+        .unwrap_or_else(|| instruction.program_id.clone());
+    let name = match &instruction.name {
+        Some(name) => name.cyan().to_string(),
+        None => "Unknown instruction".dimmed().to_string(),
+    };
+    println!(
+        "{} {} {}",
+        format!("Failed instruction [ix {}]:", instruction.index).bold(),
+        name,
+        format!("on {}", program).dimmed()
+    );
+
+    if instruction.name.is_some() || instruction.decode_error.is_some() {
+        println!("  {}", "Args:".bold());
+    }
+    print_fields(instruction.args.as_ref(), instruction.decode_error.as_deref(), &instruction.data_base64);
+
+    if instruction.accounts.is_empty() {
+        return;
+    }
+    println!("  {}", "Accounts:".bold());
+    for (i, account) in instruction.accounts.iter().enumerate() {
+        let mut flags = Vec::new();
+        if account.signer {
+            flags.push("signer");
+        }
+        if account.writable {
+            flags.push("writable");
+        }
+
+        let mut mismatches = Vec::new();
+        if account.expected_signer == Some(true) && !account.signer {
+            mismatches.push("expected signer");
+        }
+        if account.expected_writable == Some(true) && !account.writable {
+            mismatches.push("expected writable");
+        }
+
+        let label = account.name.clone().unwrap_or_else(|| format!("#{}", i));
+        let mut line = format!("      {}: {}", label, account.pubkey);
+        if !flags.is_empty() {
+            line.push_str(&format!(" {}", format!("({})", flags.join(", ")).dimmed()));
+        }
+        if !mismatches.is_empty() {
+            line.push_str(&format!(" {}", format!("<- {}", mismatches.join(", ")).red()));
+        }
+        println!("{}", line);
+    }
+}
+
+/// Print decoded fields one per line, the decode error, or the raw data.
+fn print_fields(fields: Option<&serde_json::Value>, decode_error: Option<&str>, data_base64: &str) {
+    match (fields, decode_error) {
+        (Some(serde_json::Value::Object(fields)), _) => {
+            for (key, value) in fields {
+                let value = match value {
+                    serde_json::Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                println!("      {}: {}", key, value);
+            }
+        }
+        (Some(other), _) => println!("      {}", other),
+        (None, Some(error)) => println!("      {} {}", "Could not decode:".yellow(), error),
+        (None, None) => println!("      {}", data_base64.dimmed()),
+    }
+}
+
+pub fn format_watched_transaction(tx: &WatchedTransaction) {
+    let status = if tx.failed { "✗".red() } else { "✓".green() };
+    println!("{} {} {}", status, tx.signature.bold(), format!("(slot {})", tx.slot).dimmed());
+
+    if tx.logs_truncated {
+        format_truncation_warning();
+    }
+    for error in &tx.errors {
+        format_error(error, false);
+    }
+    println!();
+}
+
+/// One line per transaction, then how often each error and program came up.
+pub fn format_batch(report: &BatchReport) {
+    for entry in &report.results {
+        let truncated = match &entry.report {
+            Some(tx) if tx.logs_truncated => format!("  {}", "(logs truncated)".yellow()),
+            _ => String::new(),
+        };
+        match (&entry.report, &entry.error) {
+            (Some(tx), _) if tx.errors.is_empty() => println!("{} {}{}", "✓".green(), entry.signature, truncated),
+            (Some(tx), _) => {
+                let mut causes: Vec<String> = Vec::new();
+                for error in &tx.errors {
+                    let cause = format!(
+                        "{} ({})",
+                        error.error_name.as_deref().unwrap_or(&error.error_hex),
+                        ohsol::decoder::registry::get_program_name(&error.program_id)
+                            .unwrap_or_else(|| error.program_id.clone())
+                    );
+                    if !causes.contains(&cause) {
+                        causes.push(cause);
+                    }
+                }
+                println!("{} {}  {}{}", "✗".red(), entry.signature, causes.join(", ").red(), truncated);
+            }
+            (None, error) => println!(
+                "{} {}  {}",
+                "?".yellow(),
+                entry.signature,
+                error.as_ref().map_or("lookup failed".to_string(), |e| e.message.clone()).yellow()
+            ),
+        }
+    }
+
+    let summary = &report.summary;
+    println!(
+        "\n{} transaction(s): {} failed, {} succeeded, {} could not be fetched",
+        summary.transactions,
+        summary.failed.to_string().red(),
+        (summary.transactions - summary.failed - summary.lookup_errors).to_string().green(),
+        summary.lookup_errors.to_string().yellow()
+    );
+
+    if !summary.errors.is_empty() {
+        println!("\n{}", "Errors:".bold());
+        print_tallies("Error", &summary.errors);
+        println!("\n{}", "Programs:".bold());
+        print_tallies("Program", &summary.programs);
+    }
+}
+
+pub fn format_scan(report: &ScanReport) {
+    use comfy_table::{Table, presets::UTF8_FULL};
+
+    let summary = &report.summary;
+    println!(
+        "{} of {} transaction(s) failed",
+        summary.transactions.to_string().red().bold(),
+        report.scanned
+    );
+    if summary.lookup_errors > 0 {
+        println!("{}", format!("{} could not be fetched", summary.lookup_errors).yellow());
+    }
+    if summary.transactions == 0 {
+        return;
+    }
+
+    if !report.timeline.is_empty() {
+        let hourly = report.bucket_secs < 86400;
+        println!("\n{}", if hourly { "Failures per hour (UTC):" } else { "Failures per day (UTC):" }.bold());
+
+        let peak = report.timeline.iter().map(|b| b.failed).max().unwrap_or(1);
+        let mut table = Table::new();
+        table.load_preset(UTF8_FULL);
+        table.set_header(vec!["Time", "Failed", "", "Top causes"]);
+        for bucket in &report.timeline {
+            let causes: Vec<String> = bucket
+                .errors
+                .iter()
+                .take(3)
+                .map(|t| format!("{} ×{}", t.label, t.count))
+                .collect();
+            table.add_row(vec![
+                format_utc(bucket.start, hourly),
+                bucket.failed.to_string(),
+                "█".repeat((bucket.failed * 20).div_ceil(peak)),
+                causes.join(", "),
+            ]);
+        }
+        println!("{}", table);
+    }
+
+    if !summary.errors.is_empty() {
+        println!("\n{}", "Errors:".bold());
+        print_tallies("Error", &summary.errors);
+        println!("\n{}", "Programs:".bold());
+        print_tallies("Program", &summary.programs);
+    }
+}
+
+/// `2024-05-01` or `2024-05-01 13:00` for a Unix time, in UTC.
+fn format_utc(secs: i64, with_hour: bool) -> String {
+    let days = secs.div_euclid(86400);
+    let hour = secs.rem_euclid(86400) / 3600;
+
+    // Days since 1970-01-01 to a civil date (Howard Hinnant's algorithm).
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    if with_hour {
+        format!("{:04}-{:02}-{:02} {:02}:00", year, month, day, hour)
+    } else {
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+/// The compute budget, where the units went, and why they ran out if they did.
+pub fn format_compute(report: &TransactionReport) {
+    use comfy_table::{Table, presets::UTF8_FULL};
+
+    let compute = &report.compute;
+    let budget = &compute.budget;
+    println!("{}", "Compute:".bold());
+
+    match (compute.limit, budget.unit_limit) {
+        (Some(limit), Some(_)) => println!("  Limit: {} CU (SetComputeUnitLimit)", limit),
+        (Some(limit), None) => println!("  Limit: {} CU (default)", limit),
+        (None, _) => {}
+    }
+    if let Some(used) = compute.used {
+        match compute.limit.filter(|limit| *limit > 0) {
+            Some(limit) => println!("  Used:  {} CU ({:.1}%)", used, used as f64 * 100.0 / limit as f64),
+            None => println!("  Used:  {} CU", used),
+        }
+    }
+    if let Some(price) = budget.unit_price {
+        let fee = compute
+            .priority_fee_lamports
+            .map(|fee| format!(", priority fee {} lamports", fee))
+            .unwrap_or_default();
+        println!("  Price: {} micro-lamports/CU{}", price, fee);
+    }
+    if let Some(bytes) = budget.heap_frame_bytes {
+        println!("  Heap:  {} KiB", bytes / 1024);
+    }
+    if let Some(bytes) = budget.loaded_accounts_data_size_limit {
+        println!("  Loaded accounts data limit: {} bytes", bytes);
+    }
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(vec!["Program", "Used", "Allotted", "Self", "CPIs"]);
+    fn add_rows(table: &mut Table, invocation: &Invocation) {
+        let program = ohsol::decoder::registry::get_program_name(&invocation.program_id)
+            .unwrap_or_else(|| invocation.program_id.clone());
+        let label = format!(
+            "{}{}{}",
+            "  ".repeat(invocation.depth.saturating_sub(1) as usize),
+            if invocation.depth == 1 { format!("#{} ", invocation.instruction_index) } else { String::new() },
+            program
+        );
+        let units = |value: Option<u64>| value.map_or("-".to_string(), |v| v.to_string());
+        let children = invocation.children_compute_used();
+        table.add_row(vec![
+            label,
+            units(invocation.compute_used),
+            units(invocation.compute_total),
+            units(invocation.self_compute_used()),
+            if children > 0 { children.to_string() } else { "-".to_string() },
+        ]);
+        for child in &invocation.children {
+            add_rows(table, child);
+        }
+    }
+    for root in &report.invocations {
+        add_rows(&mut table, root);
+    }
+    if !report.invocations.is_empty() {
+        println!("{}", table);
+    }
+
+    if let Some(diagnosis) = &compute.diagnosis {
+        println!("{} {}", "Diagnosis:".red().bold(), diagnosis);
+    }
+}
+
+fn print_tallies(label: &str, tallies: &[Tally]) {
+    use comfy_table::{Table, presets::UTF8_FULL};
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(vec![label, "Transactions"]);
+    for tally in tallies {
+        table.add_row(vec![tally.label.clone(), tally.count.to_string()]);
+    }
+    println!("{}", table);
+}
+
+pub fn format_invocation_tree(invocations: &[Invocation]) {
+    for invocation in invocations {
+        println!("{}", format!("Instruction #{}", invocation.instruction_index).bold());
+        print_invocation(invocation, "", true);
+    }
+}
+
+fn print_invocation(invocation: &Invocation, prefix: &str, last: bool) {
+    let branch = if last { "└─ " } else { "├─ " };
+
+    let status = match &invocation.outcome {
+        Outcome::Success => "✓".green().to_string(),
+        Outcome::Failed { error } => format!("{} {}", "✗".red(), error.red()),
+        Outcome::Incomplete => "? incomplete".yellow().to_string(),
+    };
+
+    let compute = match (invocation.compute_used, invocation.compute_total) {
+        (Some(used), Some(total)) => format!(" ({} / {} CU)", used, total).dimmed().to_string(),
+        _ => String::new(),
+    };
+
+    let program = ohsol::decoder::registry::get_program_name(&invocation.program_id)
+        .map(|name| format!("{} ({})", invocation.program_id, name))
+        .unwrap_or_else(|| invocation.program_id.clone());
+
+    println!("{}{}{} {}{}", prefix, branch, program.cyan(), status, compute);
+
+    let child_prefix = format!("{}{}", prefix, if last { "   " } else { "│  " });
+    let child_count = invocation.logs.len() + invocation.children.len();
+    let mut printed = 0;
+
+    // Logs and CPIs are stored separately; show logs first, then CPIs.
+    for log in &invocation.logs {
+        printed += 1;
+        let branch = if printed == child_count { "└─ " } else { "├─ " };
+        println!("{}{}{}", child_prefix, branch, log.dimmed());
+    }
+
+    for child in &invocation.children {
+        printed += 1;
+        print_invocation(child, &child_prefix, printed == child_count);
+    }
+}
+
+#[derive(Serialize)]
+pub struct CacheSummary {
+    pub program_id: String,
+    pub network: String,
+    pub name: Option<String>,
+    pub errors: usize,
+    pub slot: u64,
+    pub fetched_at: u64,
+    pub age_secs: u64,
+}
+
+pub fn cache_summaries(entries: &[CacheEntry]) -> Vec<CacheSummary> {
+    entries
+        .iter()
+        .map(|entry| {
+            let idl = entry.parse_idl().and_then(Result::ok);
+            CacheSummary {
+                program_id: entry.program_id.clone(),
+                network: entry.network.clone(),
+                name: idl.as_ref().map(|idl| idl.get_name()),
+                errors: idl.as_ref().map_or(0, |idl| idl.errors.len()),
+                slot: entry.slot,
+                fetched_at: entry.fetched_at,
+                age_secs: entry.age().as_secs(),
+            }
+        })
+        .collect()
+}
+
+pub fn format_cache_entries(entries: &[CacheEntry]) {
+    use comfy_table::{Table, presets::UTF8_FULL};
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(vec!["Program", "Network", "Name", "Errors", "Slot", "Age"]);
+
+    for summary in cache_summaries(entries) {
+        table.add_row(vec![
+            summary.program_id,
+            summary.network,
+            summary.name.unwrap_or_else(|| "(no IDL)".to_string()),
+            summary.errors.to_string(),
+            summary.slot.to_string(),
+            format_age(summary.age_secs),
+        ]);
+    }
+
+    println!("{}", table);
+}
+
+fn format_age(secs: u64) -> String {
+    match secs {
+        s if s < 60 => format!("{}s", s),
+        s if s < 3600 => format!("{}m", s / 60),
+        s if s < 86400 => format!("{}h", s / 3600),
+        s => format!("{}d", s / 86400),
+    }
+}
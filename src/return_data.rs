@@ -0,0 +1,172 @@
+use crate::decoder::Decoder;
+use crate::idl::borsh::BorshReader;
+use crate::log_parser::{parse_log_line, LogEntry};
+use base64::Engine;
+use serde::Serialize;
+use solana_sdk::message::VersionedMessage;
+
+/// Data a program set with `set_return_data`, typed with the program's IDL
+/// when possible.
+#[derive(Serialize, Debug, Clone)]
+pub struct ReturnData {
+    pub program_id: String,
+    pub data_base64: String,
+    pub data_len: usize,
+    /// The IDL instruction whose `returns` type was used to decode the data.
+    pub instruction: Option<String>,
+    /// The decoded value, or `None` if no IDL type applied.
+    pub value: Option<serde_json::Value>,
+    /// Why the data couldn't be decoded, if a type applied.
+    pub decode_error: Option<String>,
+}
+
+/// The last `Program return:` line of `logs`, as `(program_id, base64 data)`.
+/// The runtime keeps only the most recent return data, so earlier lines are
+/// superseded.
+pub fn return_data_from_logs(logs: &[String]) -> Option<(String, String)> {
+    logs.iter()
+        .rev()
+        .find_map(|line| match parse_log_line(line) {
+            LogEntry::Return { program_id, data } => Some((program_id, data)),
+            _ => None,
+        })
+}
+
+/// Decode the return data of a transaction from its logs. See
+/// [`decode_return_data`] for how `message` and `decoder` are used.
+pub fn transaction_return_data(
+    logs: &[String],
+    message: Option<&VersionedMessage>,
+    decoder: &Decoder,
+) -> Option<ReturnData> {
+    let (program_id, data) = return_data_from_logs(logs)?;
+    Some(decode_return_data(&program_id, &data, message, decoder))
+}
+
+/// Decode `data_base64` returned by `program_id`.
+///
+/// The type comes from the IDL instruction that returned it: the last
+/// top-level instruction of `message` calling the program, or, when that isn't
+/// known (no message, or the data was returned from a CPI), the program's only
+/// instruction that declares `returns`. If `decoder` has an RPC URL, IDLs of
+/// programs it doesn't know are fetched.
+pub fn decode_return_data(
+    program_id: &str,
+    data_base64: &str,
+    message: Option<&VersionedMessage>,
+    decoder: &Decoder,
+) -> ReturnData {
+    let bytes = base64::engine::general_purpose::STANDARD.decode(data_base64);
+    let mut return_data = ReturnData {
+        program_id: program_id.to_string(),
+        data_base64: data_base64.to_string(),
+        data_len: bytes.as_ref().map_or(0, Vec::len),
+        instruction: None,
+        value: None,
+        decode_error: None,
+    };
+
+    let Some(idl) = decoder.program_idl(program_id) else {
+        return return_data;
+    };
+
+    let called = message.and_then(|message| {
+        let keys = message.static_account_keys();
+        message
+            .instructions()
+            .iter()
+            .rev()
+            .find(|ix| {
+                keys.get(ix.program_id_index as usize)
+                    .is_some_and(|key| key.to_string() == program_id)
+            })
+            .and_then(|ix| idl.find_instruction(&ix.data))
+            .filter(|ix| ix.returns.is_some())
+    });
+    let instruction = called.or_else(|| {
+        let mut returning = idl.instructions.iter().filter(|ix| ix.returns.is_some());
+        returning.next().filter(|_| returning.next().is_none())
+    });
+    let Some((instruction, ty)) = instruction.and_then(|ix| Some((ix, ix.returns.as_ref()?)))
+    else {
+        return return_data;
+    };
+
+    return_data.instruction = Some(instruction.name.clone());
+    match bytes {
+        Ok(bytes) => match BorshReader::new(&idl, &bytes).read_type(ty) {
+            Ok(value) => return_data.value = Some(value),
+            Err(e) => return_data.decode_error = Some(e),
+        },
+        Err(e) => return_data.decode_error = Some(format!("invalid base64: {}", e)),
+    }
+    return_data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::idl::parse_idl;
+    use solana_sdk::instruction::Instruction;
+    use solana_sdk::message::Message;
+    use solana_sdk::pubkey::Pubkey;
+
+    #[test]
+    fn test_decode_return_data_with_idl() {
+        let program_id = Pubkey::new_unique();
+        let idl = parse_idl(
+            r#"{
+                "version": "0.1.0", "name": "pricing",
+                "instructions": [
+                    { "name": "quote", "accounts": [], "args": [], "returns": "u64" },
+                    { "name": "quoteRange", "accounts": [], "args": [], "returns": { "vec": "u16" } },
+                    { "name": "refresh", "accounts": [], "args": [] }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let decoder = Decoder::new();
+        decoder
+            .registry()
+            .store_idl(&program_id.to_string(), idl.clone());
+
+        let engine = base64::engine::general_purpose::STANDARD;
+        let logs: Vec<String> = [
+            format!("Program {} invoke [1]", program_id),
+            format!(
+                "Program return: {} {}",
+                program_id,
+                engine.encode(1u64.to_le_bytes())
+            ),
+            format!("Program {} success", program_id),
+            format!("Program {} invoke [1]", program_id),
+            format!(
+                "Program return: {} {}",
+                program_id,
+                engine.encode(1_500u64.to_le_bytes())
+            ),
+            format!("Program {} success", program_id),
+        ]
+        .to_vec();
+
+        let payer = Pubkey::new_unique();
+        let message = VersionedMessage::Legacy(Message::new(
+            &[Instruction::new_with_bytes(
+                program_id,
+                &idl.instructions[0].discriminator(),
+                vec![],
+            )],
+            Some(&payer),
+        ));
+
+        let decoded = transaction_return_data(&logs, Some(&message), &decoder).unwrap();
+        assert_eq!(decoded.instruction.as_deref(), Some("quote"));
+        assert_eq!(decoded.value, Some(serde_json::json!(1500)));
+        assert_eq!(decoded.data_len, 8);
+
+        // Two instructions declare a return type, so without the message the
+        // type is ambiguous.
+        let decoded = transaction_return_data(&logs, None, &decoder).unwrap();
+        assert_eq!((decoded.instruction, decoded.value), (None, None));
+    }
+}
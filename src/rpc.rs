@@ -1,100 +1,521 @@
-use crate::log_parser::{parse_logs, LogEntry};
-use crate::output::DecodedError;
-use crate::decoder;
-use solana_client::rpc_client::RpcClient;
-use solana_sdk::commitment_config::CommitmentConfig;
+use crate::compute::analyze_compute;
+use crate::decoder::Decoder;
+use crate::events::decode_log_events;
+use crate::instruction::decode_message_instruction;
+use crate::invocation::build_invocation_tree;
+use crate::log_parser::logs_truncated;
+use crate::return_data::{decode_return_data, transaction_return_data};
+use crate::transaction::{decode_transaction, failing_instruction_index, TransactionReport};
+use crate::network::Endpoint;
+use crate::{DecodedError, OhsolError, Result};
+use solana_client::rpc_client::{RpcClient, RpcClientConfig};
+use solana_client::rpc_request::RpcRequest;
+use solana_rpc_client::http_sender::HttpSender;
+use solana_sdk::commitment_config::{CommitmentConfig, CommitmentLevel};
 use solana_sdk::signature::Signature;
+use solana_sdk::address_lookup_table::state::AddressLookupTable;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::CompiledInstruction;
+use solana_sdk::message::v0::{self, LoadedAddresses, MessageAddressTableLookup};
+use solana_sdk::message::{Message, VersionedMessage};
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, EncodedTransaction, EncodedTransactionWithStatusMeta, UiInstruction, UiLoadedAddresses, UiMessage,
+    UiParsedInstruction, UiTransaction,
+};
+use std::collections::BTreeMap;
 use std::str::FromStr;
+use std::sync::{LazyLock, RwLock};
+use std::time::Duration;
 
-pub fn get_rpc_url(provided: Option<String>) -> String {
-    provided.unwrap_or_else(|| {
-        std::env::var("SOLANA_RPC_URL")
-            .unwrap_or_else(|_| "https://api.mainnet-beta.solana.com".to_string())
+/// How RPC clients are built: the commitment and extra headers of the active
+/// endpoint. Process-wide, like the error registry, so every fetch (IDLs,
+/// transactions, lookup tables) picks it up without threading it through.
+#[derive(Debug, Clone)]
+struct ClientOptions {
+    commitment: CommitmentLevel,
+    headers: BTreeMap<String, String>,
+}
+
+static CLIENT_OPTIONS: LazyLock<RwLock<ClientOptions>> = LazyLock::new(|| {
+    RwLock::new(ClientOptions {
+        commitment: CommitmentLevel::Confirmed,
+        headers: BTreeMap::new(),
     })
+});
+
+/// Use `endpoint`'s commitment and headers for every RPC client created from
+/// now on.
+pub fn configure(endpoint: &Endpoint) -> Result<()> {
+    for (name, value) in &endpoint.headers {
+        reqwest::header::HeaderName::from_str(name)
+            .map_err(|e| OhsolError::Rpc(format!("Invalid header name '{}': {}", name, e)))?;
+        reqwest::header::HeaderValue::from_str(value)
+            .map_err(|e| OhsolError::Rpc(format!("Invalid value for header '{}': {}", name, e)))?;
+    }
+
+    *CLIENT_OPTIONS.write().unwrap() = ClientOptions {
+        commitment: endpoint.commitment,
+        headers: endpoint.headers.clone(),
+    };
+    Ok(())
+}
+
+/// The commitment requests are made at.
+pub fn commitment() -> CommitmentConfig {
+    CommitmentConfig {
+        commitment: CLIENT_OPTIONS.read().unwrap().commitment,
+    }
+}
+
+/// The commitment for transaction history (`getTransaction`,
+/// `getSignaturesForAddress`), which nodes only serve at `confirmed` or above.
+pub fn history_commitment() -> CommitmentConfig {
+    match commitment().commitment {
+        CommitmentLevel::Processed => CommitmentConfig::confirmed(),
+        _ => commitment(),
+    }
+}
+
+/// An RPC client for `rpc_url` with the configured commitment and headers.
+pub fn client(rpc_url: &str) -> RpcClient {
+    let options = CLIENT_OPTIONS.read().unwrap().clone();
+    let config = RpcClientConfig::with_commitment(CommitmentConfig {
+        commitment: options.commitment,
+    });
+    if options.headers.is_empty() {
+        return RpcClient::new_with_commitment(rpc_url.to_string(), config.commitment_config);
+    }
+
+    let mut headers = HttpSender::default_headers();
+    for (name, value) in &options.headers {
+        // Validated in `configure`.
+        if let (Ok(name), Ok(value)) = (
+            reqwest::header::HeaderName::from_str(name),
+            reqwest::header::HeaderValue::from_str(value),
+        ) {
+            headers.insert(name, value);
+        }
+    }
+    // Same timeouts as `HttpSender::new`.
+    let timeout = Duration::from_secs(30);
+    let http = reqwest::Client::builder()
+        .default_headers(headers)
+        .timeout(timeout)
+        .pool_idle_timeout(timeout)
+        .build()
+        .expect("build rpc client");
+    RpcClient::new_sender(HttpSender::new_with_client(rpc_url, http), config)
 }
 
 pub fn fetch_transaction_errors(
-    rpc_url: &str,
+    decoder: &Decoder,
     signature: &str,
-) -> anyhow::Result<Vec<DecodedError>> {
+) -> Result<Vec<DecodedError>> {
+    let report = fetch_transaction_report(decoder, signature)?;
+
+    if report.errors.is_empty() {
+        return Err(OhsolError::NoErrors);
+    }
+
+    Ok(report.errors)
+}
+
+/// Fetch a transaction through `decoder`'s RPC URL and decode it.
+pub fn fetch_transaction_report(decoder: &Decoder, signature: &str) -> Result<TransactionReport> {
+    let rpc_url = decoder.rpc_url().ok_or(OhsolError::NoRpcUrl)?;
+    fetch_transaction_report_with(&client(rpc_url), decoder, signature)
+}
+
+/// Like [`fetch_transaction_report`], through an existing `client`, so many
+/// lookups can share its connections.
+pub fn fetch_transaction_report_with(client: &RpcClient, decoder: &Decoder, signature: &str) -> Result<TransactionReport> {
     let sig = Signature::from_str(signature)
-        .map_err(|e| anyhow::anyhow!("Invalid signature: {}", e))?;
+        .map_err(|e| OhsolError::InvalidSignature(format!("{}: {}", signature, e)))?;
 
-    let client = RpcClient::new_with_commitment(rpc_url.to_string(), CommitmentConfig::confirmed());
+    let config = solana_client::rpc_config::RpcTransactionConfig {
+        // Binary, so the message (and the failing instruction) can be decoded.
+        encoding: Some(solana_transaction_status::UiTransactionEncoding::Base64),
+        commitment: Some(history_commitment()),
+        max_supported_transaction_version: Some(0),
+    };
+    // Sent raw, since the node answers `null` for signatures it doesn't know
+    // and `get_transaction_with_config` can't represent that.
+    let tx: Option<EncodedConfirmedTransactionWithStatusMeta> = client
+        .send(RpcRequest::GetTransaction, serde_json::json!([sig.to_string(), config]))
+        .map_err(|e| OhsolError::Rpc(format!("Failed to fetch transaction: {}", e)))?;
+    let tx = tx.ok_or_else(|| OhsolError::TransactionNotFound(signature.to_string()))?;
 
-    let tx = client
-        .get_transaction_with_config(
-            &sig,
-            solana_client::rpc_config::RpcTransactionConfig {
-                encoding: Some(solana_transaction_status::UiTransactionEncoding::Json),
-                commitment: Some(CommitmentConfig::confirmed()),
-                max_supported_transaction_version: Some(0),
-            },
-        )
-        .map_err(|e| anyhow::anyhow!("Failed to fetch transaction: {}", e))?;
+    build_transaction_report(&tx.transaction, Some(signature), decoder, Some(client))
+}
 
+/// Decode a transaction saved as JSON, without fetching it: a `getTransaction`
+/// result (bare or in its JSON-RPC envelope), the output of
+/// `solana confirm -v --output json`, or just an array of log lines.
+///
+/// Address lookup tables aren't read, so a v0 transaction's failing instruction
+/// only shows looked-up accounts if the metadata lists them. `decoder`'s RPC
+/// URL is used for nothing but the IDLs of programs its registry doesn't know.
+pub fn transaction_report_from_json(json: &str, decoder: &Decoder) -> Result<TransactionReport> {
+    let invalid = |e: serde_json::Error| OhsolError::InvalidTransaction(format!("Invalid transaction JSON: {}", e));
+    let mut value: serde_json::Value = serde_json::from_str(json).map_err(invalid)?;
+
+    if let Some(result) = value.get_mut("result").map(serde_json::Value::take) {
+        if result.is_null() {
+            return Err(OhsolError::InvalidTransaction(
+                "The saved response has no transaction (the node returned null)".to_string(),
+            ));
+        }
+        value = result;
+    }
+
+    if value.is_array() {
+        let logs: Vec<String> = serde_json::from_value(value).map_err(invalid)?;
+        let errors = decode_transaction(&logs, None, &[], decoder);
+        let invocations = build_invocation_tree(&logs);
+        return Ok(TransactionReport {
+            signature: String::new(),
+            events: decode_log_events(&logs, decoder),
+            failed_instruction: None,
+            compute: analyze_compute(None, &invocations, &errors, None, decoder.registry()),
+            return_data: transaction_return_data(&logs, None, decoder),
+            logs_truncated: logs_truncated(&logs),
+            errors,
+            invocations,
+        });
+    }
+
+    // Both the RPC result and `solana confirm` put `transaction` and `meta` at
+    // the top level; the other fields are ignored.
+    let tx: EncodedTransactionWithStatusMeta = serde_json::from_value(value).map_err(invalid)?;
+    build_transaction_report(&tx, None, decoder, None)
+}
+
+/// Decode a fetched or saved transaction. With a `client`, lookup tables the
+/// metadata doesn't resolve are read from it.
+fn build_transaction_report(
+    tx: &EncodedTransactionWithStatusMeta,
+    signature: Option<&str>,
+    decoder: &Decoder,
+    client: Option<&RpcClient>,
+) -> Result<TransactionReport> {
     let meta = tx
-        .transaction
         .meta
-        .ok_or_else(|| anyhow::anyhow!("Transaction has no metadata"))?;
+        .as_ref()
+        .ok_or_else(|| OhsolError::Rpc("Transaction has no metadata".to_string()))?;
 
-    let logs = match meta.log_messages {
-        solana_transaction_status::option_serializer::OptionSerializer::Some(logs) => logs,
+    let logs = match &meta.log_messages {
+        OptionSerializer::Some(logs) => logs.clone(),
         _ => vec![],
     };
-    let parsed_logs = parse_logs(&logs);
-
-    let mut errors = Vec::new();
-    let mut current_program: Option<String> = None;
-    let mut current_depth: u8 = 0;
-
-    for entry in parsed_logs {
-        match entry {
-            LogEntry::Invoke { program_id, depth } => {
-                current_program = Some(program_id);
-                current_depth = depth;
-            }
-            LogEntry::AnchorError {
-                file,
-                line,
-                code_name,
-                code_number,
-                message,
-            } => {
-                let mut error = DecodedError::new(code_number)
-                    .with_name(code_name)
-                    .with_message(message)
-                    .with_source(file, line)
-                    .with_cpi_depth(current_depth);
-
-                if let Some(ref pid) = current_program {
-                    error = error.with_program(pid.clone());
-                }
-
-                errors.push(error);
-            }
-            LogEntry::CustomError { hex_code } => {
-                let code = u32::from_str_radix(&hex_code, 16).unwrap_or(0);
-                let mut error = decoder::decode_error(code, current_program.as_deref());
-                error = error.with_cpi_depth(current_depth);
-                errors.push(error);
-            }
-            LogEntry::Failed { program_id, error: error_msg } => {
-                // Extract error code from the error message if it's a custom error
-                if let Some(hex_code) = error_msg.strip_prefix("custom program error: 0x") {
-                    let code = u32::from_str_radix(hex_code, 16).unwrap_or(0);
-                    let mut error = decoder::decode_error(code, Some(&program_id));
-                    error = error.with_cpi_depth(current_depth);
-                    errors.push(error);
-                }
-            }
-            _ => {}
+
+    let program_ids = instruction_program_ids(&tx.transaction);
+    let errors = decode_transaction(&logs, meta.err.as_ref(), &program_ids, decoder);
+
+    let message = decode_message(&tx.transaction);
+    let failed_instruction = match (&message, meta.err.as_ref().and_then(failing_instruction_index)) {
+        (Some(message), Some(index)) => {
+            // Nodes report the addresses a v0 transaction loaded; older ones
+            // don't, so fall back to reading the lookup tables.
+            let loaded = match &meta.loaded_addresses {
+                OptionSerializer::Some(loaded) => parse_loaded_addresses(loaded),
+                _ => client.and_then(|client| fetch_loaded_addresses(client, message).ok()),
+            };
+            decode_message_instruction(message, loaded.as_ref(), index, decoder)
+        }
+        _ => None,
+    };
+
+    let invocations = build_invocation_tree(&logs);
+    let units_consumed = match meta.compute_units_consumed {
+        OptionSerializer::Some(units) => Some(units),
+        _ => None,
+    };
+
+    // The node's copy survives truncated logs; older nodes don't report it.
+    let return_data = match &meta.return_data {
+        OptionSerializer::Some(return_data) => {
+            Some(decode_return_data(&return_data.program_id, &return_data.data.0, message.as_ref(), decoder))
         }
+        _ => transaction_return_data(&logs, message.as_ref(), decoder),
+    };
+
+    Ok(TransactionReport {
+        signature: signature
+            .map(str::to_string)
+            .or_else(|| first_signature(&tx.transaction))
+            .unwrap_or_default(),
+        events: decode_log_events(&logs, decoder),
+        failed_instruction,
+        compute: analyze_compute(message.as_ref(), &invocations, &errors, units_consumed, decoder.registry()),
+        return_data,
+        logs_truncated: logs_truncated(&logs),
+        errors,
+        invocations,
+    })
+}
+
+/// The message of a binary or raw JSON encoded transaction. Parsed (`jsonParsed`)
+/// messages drop the instruction data, so they can't be decoded.
+fn decode_message(transaction: &EncodedTransaction) -> Option<VersionedMessage> {
+    if let Some(versioned) = transaction.decode() {
+        return Some(versioned.message);
+    }
+    let EncodedTransaction::Json(UiTransaction {
+        message: UiMessage::Raw(raw),
+        ..
+    }) = transaction
+    else {
+        return None;
+    };
+
+    let pubkey = |key: &String| Pubkey::from_str(key).ok();
+    let account_keys = raw.account_keys.iter().map(pubkey).collect::<Option<Vec<_>>>()?;
+    let recent_blockhash = Hash::from_str(&raw.recent_blockhash).ok()?;
+    let instructions = raw
+        .instructions
+        .iter()
+        .map(|ix| {
+            Some(CompiledInstruction {
+                program_id_index: ix.program_id_index,
+                accounts: ix.accounts.clone(),
+                data: bs58::decode(&ix.data).into_vec().ok()?,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(match &raw.address_table_lookups {
+        Some(lookups) => VersionedMessage::V0(v0::Message {
+            header: raw.header,
+            account_keys,
+            recent_blockhash,
+            instructions,
+            address_table_lookups: lookups
+                .iter()
+                .map(|lookup| {
+                    Some(MessageAddressTableLookup {
+                        account_key: pubkey(&lookup.account_key)?,
+                        writable_indexes: lookup.writable_indexes.clone(),
+                        readonly_indexes: lookup.readonly_indexes.clone(),
+                    })
+                })
+                .collect::<Option<Vec<_>>>()?,
+        }),
+        None => VersionedMessage::Legacy(Message {
+            header: raw.header,
+            account_keys,
+            recent_blockhash,
+            instructions,
+        }),
+    })
+}
+
+fn first_signature(transaction: &EncodedTransaction) -> Option<String> {
+    match transaction {
+        EncodedTransaction::Json(ui_tx) => ui_tx.signatures.first().cloned(),
+        _ => transaction
+            .decode()
+            .and_then(|versioned| versioned.signatures.first().map(|s| s.to_string())),
     }
+}
+
+/// Resolve the addresses a v0 message loads from address lookup tables by
+/// reading the tables. Legacy messages load none.
+pub fn fetch_loaded_addresses(client: &RpcClient, message: &VersionedMessage) -> Result<LoadedAddresses> {
+    let mut loaded = LoadedAddresses::default();
+    let Some(lookups) = message.address_table_lookups().filter(|l| !l.is_empty()) else {
+        return Ok(loaded);
+    };
+
+    let table_keys: Vec<Pubkey> = lookups.iter().map(|lookup| lookup.account_key).collect();
+    let accounts = client
+        .get_multiple_accounts(&table_keys)
+        .map_err(|e| OhsolError::Rpc(format!("Failed to fetch address lookup tables: {}", e)))?;
 
-    if errors.is_empty() {
-        return Err(anyhow::anyhow!("No errors found in transaction"));
+    // Writable addresses from every table come first, then readonly ones, the
+    // order the runtime appends them to the account keys.
+    for (lookup, account) in lookups.iter().zip(accounts) {
+        let account = account.ok_or_else(|| {
+            OhsolError::Rpc(format!("Address lookup table {} not found", lookup.account_key))
+        })?;
+        let table = AddressLookupTable::deserialize(&account.data).map_err(|e| {
+            OhsolError::Rpc(format!("Invalid address lookup table {}: {}", lookup.account_key, e))
+        })?;
+        let resolve = |indexes: &[u8]| -> Result<Vec<Pubkey>> {
+            indexes
+                .iter()
+                .map(|&i| {
+                    table.addresses.get(i as usize).copied().ok_or_else(|| {
+                        OhsolError::Rpc(format!(
+                            "Address lookup table {} has no entry {}",
+                            lookup.account_key, i
+                        ))
+                    })
+                })
+                .collect()
+        };
+        loaded.writable.extend(resolve(&lookup.writable_indexes)?);
+        loaded.readonly.extend(resolve(&lookup.readonly_indexes)?);
     }
 
-    Ok(errors)
+    Ok(loaded)
+}
+
+/// Parse the `loadedAddresses` a node reports in transaction metadata.
+fn parse_loaded_addresses(loaded: &UiLoadedAddresses) -> Option<LoadedAddresses> {
+    let parse = |keys: &[String]| keys.iter().map(|k| Pubkey::from_str(k).ok()).collect::<Option<Vec<_>>>();
+    Some(LoadedAddresses {
+        writable: parse(&loaded.writable)?,
+        readonly: parse(&loaded.readonly)?,
+    })
+}
+
+/// Program ID invoked by each top-level instruction of a transaction.
+pub fn instruction_program_ids(transaction: &EncodedTransaction) -> Vec<String> {
+    if let Some(versioned) = transaction.decode() {
+        let keys = versioned.message.static_account_keys();
+        return versioned
+            .message
+            .instructions()
+            .iter()
+            .filter_map(|ix| keys.get(ix.program_id_index as usize))
+            .map(|key| key.to_string())
+            .collect();
+    }
+
+    match transaction {
+        EncodedTransaction::Json(ui_tx) => match &ui_tx.message {
+            UiMessage::Raw(message) => message
+                .instructions
+                .iter()
+                .filter_map(|ix| message.account_keys.get(ix.program_id_index as usize).cloned())
+                .collect(),
+            UiMessage::Parsed(message) => message
+                .instructions
+                .iter()
+                .map(|ix| match ix {
+                    UiInstruction::Compiled(compiled) => message
+                        .account_keys
+                        .get(compiled.program_id_index as usize)
+                        .map(|a| a.pubkey.clone())
+                        .unwrap_or_default(),
+                    UiInstruction::Parsed(UiParsedInstruction::Parsed(p)) => p.program_id.clone(),
+                    UiInstruction::Parsed(UiParsedInstruction::PartiallyDecoded(p)) => p.program_id.clone(),
+                })
+                .collect(),
+        },
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::Engine;
+    use serde_json::json;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::transaction::VersionedTransaction;
+
+    const JUPITER: &str = "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4";
+
+    fn saved_transaction(transaction: serde_json::Value) -> serde_json::Value {
+        json!({
+            "slot": 1,
+            "blockTime": null,
+            "transaction": transaction,
+            "meta": {
+                "err": { "InstructionError": [0, { "Custom": 6001 }] },
+                "status": { "Err": { "InstructionError": [0, { "Custom": 6001 }] } },
+                "fee": 5000,
+                "preBalances": [],
+                "postBalances": [],
+                "logMessages": [
+                    format!("Program {} invoke [1]", JUPITER),
+                    "Program log: AnchorError occurred. Error Code: SlippageToleranceExceeded. Error Number: 6001. Error Message: Slippage tolerance exceeded.",
+                    format!("Program {} failed: custom program error: 0x1771", JUPITER),
+                ],
+            },
+        })
+    }
+
+    #[test]
+    fn test_transaction_report_from_saved_json() {
+        let payer = Pubkey::new_unique();
+        let message = Message::new_with_blockhash(
+            &[Instruction::new_with_bytes(
+                Pubkey::from_str(JUPITER).unwrap(),
+                &[0; 8],
+                vec![AccountMeta::new(payer, true)],
+            )],
+            Some(&payer),
+            &Hash::default(),
+        );
+        let tx = VersionedTransaction {
+            signatures: vec![Signature::from([7; 64])],
+            message: VersionedMessage::Legacy(message.clone()),
+        };
+        let base64 = base64::engine::general_purpose::STANDARD.encode(bincode::serialize(&tx).unwrap());
+
+        // A full JSON-RPC response, as saved from `getTransaction`.
+        let response = json!({ "jsonrpc": "2.0", "id": 1, "result": saved_transaction(json!([base64, "base64"])) });
+        let report = transaction_report_from_json(&response.to_string(), &Decoder::new()).unwrap();
+        assert_eq!(report.signature, Signature::from([7; 64]).to_string());
+        assert_eq!(report.errors[0].error_name.as_deref(), Some("SlippageToleranceExceeded"));
+        assert_eq!(report.failed_instruction.unwrap().program_id, JUPITER);
+
+        // `solana confirm -v --output json` prints the JSON encoding.
+        let raw = json!({
+            "signatures": [Signature::from([7; 64]).to_string()],
+            "message": {
+                "header": message.header,
+                "accountKeys": message.account_keys.iter().map(|k| k.to_string()).collect::<Vec<_>>(),
+                "recentBlockhash": Hash::default().to_string(),
+                "instructions": [{ "programIdIndex": 1, "accounts": [0], "data": bs58::encode([0; 8]).into_string(), "stackHeight": null }],
+            },
+        });
+        let report = transaction_report_from_json(&saved_transaction(raw).to_string(), &Decoder::new()).unwrap();
+        let instruction = report.failed_instruction.unwrap();
+        assert_eq!(instruction.accounts[0].pubkey, payer.to_string());
+    }
+
+    #[test]
+    fn test_transaction_report_from_logs() {
+        let logs = saved_transaction(json!(null))["meta"]["logMessages"].clone();
+        let report = transaction_report_from_json(&logs.to_string(), &Decoder::new()).unwrap();
+        assert_eq!(report.signature, "");
+        assert_eq!(report.errors[0].error_code, 6001);
+        assert!(report.failed_instruction.is_none());
+
+        assert!(matches!(
+            transaction_report_from_json(r#"{"jsonrpc":"2.0","id":1,"result":null}"#, &Decoder::new()),
+            Err(OhsolError::InvalidTransaction(_))
+        ));
+    }
+
+    #[test]
+    fn test_unknown_signature_is_not_found() {
+        use std::io::{Read, Write};
+
+        // An RPC node that answers `null`, as for a signature it doesn't know.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let rpc_url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 4096];
+            let _ = stream.read(&mut request).unwrap();
+            let body = r#"{"jsonrpc":"2.0","result":null,"id":1}"#;
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        });
+
+        let signature = Signature::from([7; 64]).to_string();
+        let result = fetch_transaction_report(&Decoder::new().with_rpc_url(rpc_url), &signature);
+        assert!(matches!(result, Err(OhsolError::TransactionNotFound(s)) if s == signature));
+    }
 }
@@ -0,0 +1,230 @@
+use crate::batch::{self, BatchEntry, BatchSummary, Tally};
+use crate::decoder::Decoder;
+use crate::rpc;
+use crate::{OhsolError, Result};
+use serde::Serialize;
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+/// Most signatures `getSignaturesForAddress` returns per call.
+const PAGE_SIZE: usize = 1000;
+
+/// Which part of an address's history to scan.
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    /// Signatures to look at, failed or not, newest first.
+    pub limit: usize,
+    /// Start from transactions older than this signature.
+    pub before: Option<String>,
+    /// Stop at this signature (exclusive).
+    pub until: Option<String>,
+    pub concurrency: usize,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            limit: PAGE_SIZE,
+            before: None,
+            until: None,
+            concurrency: batch::DEFAULT_CONCURRENCY,
+        }
+    }
+}
+
+/// A failed transaction as listed for the address, before fetching it.
+#[derive(Serialize, Debug, Clone)]
+pub struct FailedSignature {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+}
+
+/// Failed transactions in one stretch of time.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct TimeBucket {
+    /// Unix time the bucket starts at.
+    pub start: i64,
+    pub failed: usize,
+    /// Transactions per error in this bucket, most common first.
+    pub errors: Vec<Tally>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ScanReport {
+    pub address: String,
+    /// Signatures looked at, failed or not.
+    pub scanned: usize,
+    /// Length of each timeline bucket: an hour, or a day for longer spans.
+    pub bucket_secs: i64,
+    /// Oldest bucket first. Transactions without a block time are left out.
+    pub timeline: Vec<TimeBucket>,
+    pub summary: BatchSummary,
+    pub results: Vec<BatchEntry>,
+}
+
+/// Page through `address`'s recent signatures and return the failed ones,
+/// newest first, along with how many signatures were looked at.
+pub fn failed_signatures(
+    rpc_url: &str,
+    address: &str,
+    options: &ScanOptions,
+) -> Result<(usize, Vec<FailedSignature>)> {
+    let pubkey = Pubkey::from_str(address)
+        .map_err(|e| OhsolError::InvalidProgramId(format!("{}: {}", address, e)))?;
+    let parse_signature = |sig: &Option<String>| {
+        sig.as_deref()
+            .map(|s| {
+                Signature::from_str(s)
+                    .map_err(|e| OhsolError::InvalidSignature(format!("{}: {}", s, e)))
+            })
+            .transpose()
+    };
+    let mut before = parse_signature(&options.before)?;
+    let until = parse_signature(&options.until)?;
+
+    let client = rpc::client(rpc_url);
+    let mut scanned = 0;
+    let mut failed = Vec::new();
+
+    while scanned < options.limit {
+        let limit = (options.limit - scanned).min(PAGE_SIZE);
+        let page = client
+            .get_signatures_for_address_with_config(
+                &pubkey,
+                GetConfirmedSignaturesForAddress2Config {
+                    before,
+                    until,
+                    limit: Some(limit),
+                    commitment: Some(rpc::history_commitment()),
+                },
+            )
+            .map_err(|e| {
+                OhsolError::Rpc(format!("Failed to list signatures for {}: {}", address, e))
+            })?;
+
+        scanned += page.len();
+        let Some(last) = page.last() else {
+            break;
+        };
+        before =
+            Some(Signature::from_str(&last.signature).map_err(|e| {
+                OhsolError::Rpc(format!("Node returned an invalid signature: {}", e))
+            })?);
+        let exhausted = page.len() < limit;
+
+        failed.extend(
+            page.into_iter()
+                .filter(|status| status.err.is_some())
+                .map(|status| FailedSignature {
+                    signature: status.signature,
+                    slot: status.slot,
+                    block_time: status.block_time,
+                }),
+        );
+
+        if exhausted {
+            break;
+        }
+    }
+
+    Ok((scanned, failed))
+}
+
+/// Find `address`'s recent failed transactions, decode each, and break the
+/// causes down over time.
+pub fn scan_address(decoder: &Decoder, address: &str, options: &ScanOptions) -> Result<ScanReport> {
+    let rpc_url = decoder.rpc_url().ok_or(OhsolError::NoRpcUrl)?;
+    let (scanned, failed) = failed_signatures(rpc_url, address, options)?;
+    let signatures: Vec<String> = failed.iter().map(|f| f.signature.clone()).collect();
+    let batch = batch::fetch_transaction_reports(decoder, &signatures, options.concurrency)?;
+
+    let times: Vec<Option<i64>> = failed.iter().map(|f| f.block_time).collect();
+    let bucket_secs = bucket_size(&times);
+
+    Ok(ScanReport {
+        address: address.to_string(),
+        scanned,
+        bucket_secs,
+        timeline: timeline(&batch.results, &times, bucket_secs),
+        summary: batch.summary,
+        results: batch.results,
+    })
+}
+
+const HOUR: i64 = 3600;
+const DAY: i64 = 24 * HOUR;
+
+/// Hours for up to two days of history, days beyond that.
+fn bucket_size(times: &[Option<i64>]) -> i64 {
+    let times = times.iter().flatten();
+    match (times.clone().min(), times.max()) {
+        (Some(oldest), Some(newest)) if newest - oldest > 2 * DAY => DAY,
+        _ => HOUR,
+    }
+}
+
+/// Group decoded transactions into buckets by block time. `times` lines up
+/// with `entries`; transactions that failed to fetch count as failed with no
+/// known cause.
+fn timeline(entries: &[BatchEntry], times: &[Option<i64>], bucket_secs: i64) -> Vec<TimeBucket> {
+    let mut buckets: BTreeMap<i64, (usize, HashMap<String, usize>)> = BTreeMap::new();
+
+    for (entry, time) in entries.iter().zip(times) {
+        let Some(time) = time else {
+            continue;
+        };
+        let (failed, errors) = buckets
+            .entry(time - time.rem_euclid(bucket_secs))
+            .or_default();
+        *failed += 1;
+        if let Some(report) = &entry.report {
+            for label in batch::error_labels(report) {
+                *errors.entry(label).or_default() += 1;
+            }
+        }
+    }
+
+    buckets
+        .into_iter()
+        .map(|(start, (failed, errors))| TimeBucket {
+            start,
+            failed,
+            errors: batch::tallies(errors),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::decode_error;
+    use crate::transaction::TransactionReport;
+
+    #[test]
+    fn test_timeline_buckets_by_hour() {
+        let jupiter = "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4";
+        let entry = |code: u32| BatchEntry {
+            signature: String::new(),
+            report: Some(TransactionReport {
+                errors: vec![decode_error(code, Some(jupiter))],
+                ..TransactionReport::default()
+            }),
+            error: None,
+        };
+        let entries = vec![entry(6001), entry(6001), entry(6024), entry(6001)];
+        let times = vec![Some(7200 + 59), Some(7200 + 3599), Some(3600 * 5), None];
+
+        assert_eq!(bucket_size(&times), HOUR);
+        let buckets = timeline(&entries, &times, HOUR);
+        assert_eq!(buckets.len(), 2);
+        assert_eq!((buckets[0].start, buckets[0].failed), (7200, 2));
+        assert_eq!(buckets[0].errors[0].count, 2);
+        assert_eq!(buckets[1].start, 3600 * 5);
+
+        assert_eq!(bucket_size(&[Some(0), Some(3 * DAY)]), DAY);
+    }
+}
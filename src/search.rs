@@ -0,0 +1,241 @@
+use crate::decoder::registry::{ErrorEntry, Registry};
+use crate::idl::cache::IdlCache;
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// Where a search hit came from.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchSource {
+    Anchor,
+    Registry,
+    IdlCache,
+}
+
+/// An error whose name or message matched a search query.
+#[derive(Serialize, Debug, Clone)]
+pub struct ErrorMatch {
+    pub program_id: String,
+    pub program_name: Option<String>,
+    pub error_code: u32,
+    pub error_hex: String,
+    pub error_name: String,
+    pub error_message: String,
+    pub source: MatchSource,
+    /// Higher is better: exact name, then name, then message, then fuzzy hits.
+    pub score: u32,
+}
+
+/// Find errors whose name or message matches `query`, across the Anchor
+/// framework errors, every program in `registry` and the IDLs in `cache`.
+///
+/// Matching ignores case, spaces and punctuation, so `slippage exceeded`
+/// finds `SlippageExceeded`, and tolerates a one-letter typo per query word.
+/// Results are sorted best match first.
+pub fn search_errors(query: &str, registry: &Registry, cache: &IdlCache) -> Vec<ErrorMatch> {
+    let query = Query::new(query);
+    if query.compact.is_empty() {
+        return Vec::new();
+    }
+
+    let mut matches = Vec::new();
+    let mut seen = HashSet::new();
+
+    registry.with_database(|db| {
+        for entry in &db.anchor_errors {
+            if let Some(m) = query.matches("Anchor Framework", None, entry, MatchSource::Anchor) {
+                matches.push(m);
+            }
+        }
+        for (program_id, program) in &db.programs {
+            seen.insert(program_id.clone());
+            let name = Some(program.name.as_str()).filter(|n| !n.is_empty());
+            for entry in &program.errors {
+                if let Some(m) = query.matches(program_id, name, entry, MatchSource::Registry) {
+                    matches.push(m);
+                }
+            }
+        }
+    });
+
+    // Cached IDLs cover programs fetched in earlier runs; programs registered
+    // this run take precedence, and a program cached on several networks is
+    // only searched once.
+    for entry in cache.list().unwrap_or_default() {
+        if !seen.insert(entry.program_id.clone()) {
+            continue;
+        }
+        let Some(Ok(idl)) = entry.parse_idl() else {
+            continue;
+        };
+        let name = idl.get_name();
+        let program_name = Some(name.as_str()).filter(|n| !n.is_empty());
+        for error in &idl.errors {
+            let error = ErrorEntry {
+                code: error.code,
+                name: error.name.clone(),
+                msg: error.msg.clone(),
+            };
+            if let Some(m) = query.matches(
+                &entry.program_id,
+                program_name,
+                &error,
+                MatchSource::IdlCache,
+            ) {
+                matches.push(m);
+            }
+        }
+    }
+
+    matches.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| a.program_name.cmp(&b.program_name))
+            .then_with(|| a.error_code.cmp(&b.error_code))
+    });
+    matches
+}
+
+struct Query {
+    /// Lowercase letters and digits only.
+    compact: String,
+    words: Vec<String>,
+}
+
+impl Query {
+    fn new(query: &str) -> Self {
+        Self {
+            compact: compact(query),
+            words: words(query),
+        }
+    }
+
+    fn score(&self, name: &str, message: &str) -> Option<u32> {
+        let compact_name = compact(name);
+        if compact_name == self.compact {
+            return Some(100);
+        }
+        if compact_name.contains(&self.compact) {
+            return Some(80);
+        }
+        if compact(message).contains(&self.compact) {
+            return Some(60);
+        }
+
+        let candidates: Vec<String> = words(name).into_iter().chain(words(message)).collect();
+        self.words
+            .iter()
+            .all(|word| candidates.iter().any(|c| word_matches(word, c)))
+            .then_some(40)
+    }
+
+    fn matches(
+        &self,
+        program_id: &str,
+        program_name: Option<&str>,
+        entry: &ErrorEntry,
+        source: MatchSource,
+    ) -> Option<ErrorMatch> {
+        let score = self.score(&entry.name, &entry.msg)?;
+        Some(ErrorMatch {
+            program_id: program_id.to_string(),
+            program_name: program_name.map(str::to_string),
+            error_code: entry.code,
+            error_hex: format!("0x{:x}", entry.code),
+            error_name: entry.name.clone(),
+            error_message: entry.msg.clone(),
+            source,
+            score,
+        })
+    }
+}
+
+fn compact(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Lowercase words of `text`, splitting on punctuation and camelCase.
+fn words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut prev_lower = false;
+
+    for c in text.chars() {
+        if !c.is_alphanumeric() {
+            words.push(std::mem::take(&mut current));
+            prev_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && prev_lower {
+            words.push(std::mem::take(&mut current));
+        }
+        prev_lower = c.is_lowercase() || c.is_ascii_digit();
+        current.extend(c.to_lowercase());
+    }
+    words.push(current);
+
+    words.retain(|w| !w.is_empty());
+    words
+}
+
+/// A query word matches a candidate word it prefixes, or one a single edit
+/// away once it's long enough that a typo is the likely explanation.
+fn word_matches(word: &str, candidate: &str) -> bool {
+    candidate.starts_with(word) || (word.chars().count() >= 5 && within_one_edit(word, candidate))
+}
+
+fn within_one_edit(a: &str, b: &str) -> bool {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > 1 {
+        return false;
+    }
+
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let (a, b) = (&a[prefix..], &b[prefix..]);
+    match a.len().cmp(&b.len()) {
+        std::cmp::Ordering::Equal => a.get(1..) == b.get(1..),
+        std::cmp::Ordering::Less => a == &b[1..],
+        std::cmp::Ordering::Greater => &a[1..] == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_search_matches_names_messages_and_typos() {
+        let cache = IdlCache::new(
+            std::env::temp_dir().join("ohsol-search-empty"),
+            Duration::ZERO,
+        );
+        let registry = Registry::builtin();
+
+        let hits = search_errors("ConstraintSeeds", &registry, &cache);
+        assert_eq!(hits[0].error_name, "ConstraintSeeds");
+        assert_eq!(hits[0].score, 100);
+        assert_eq!(hits[0].source, MatchSource::Anchor);
+        assert_eq!(hits[0].error_hex, "0x7d6");
+
+        let by_message = search_errors("seeds constraint was violated", &registry, &cache);
+        assert!(by_message.iter().any(|m| m.error_name == "ConstraintSeeds"));
+
+        let typo = search_errors("constrant seeds", &registry, &cache);
+        assert!(typo.iter().any(|m| m.error_name == "ConstraintSeeds"));
+
+        assert!(search_errors("  ", &registry, &cache).is_empty());
+    }
+
+    #[test]
+    fn test_within_one_edit() {
+        assert!(within_one_edit("slipage", "slippage"));
+        assert!(!within_one_edit("slippgae", "slippage"));
+        assert!(within_one_edit("slippaje", "slippage"));
+        assert!(!within_one_edit("slip", "slippage"));
+    }
+}
@@ -0,0 +1,166 @@
+use crate::decoder::Decoder;
+use crate::events::{decode_log_events, DecodedEvent};
+use crate::instruction::{decode_message_instruction, DecodedInstruction};
+use crate::invocation::{build_invocation_tree, Invocation};
+use crate::log_parser::logs_truncated;
+use crate::return_data::{decode_return_data, ReturnData};
+use crate::rpc;
+use crate::transaction;
+use crate::DecodedError;
+use crate::{OhsolError, Result};
+use base64::Engine;
+use serde::Serialize;
+use solana_client::rpc_config::RpcSimulateTransactionConfig;
+use solana_sdk::transaction::VersionedTransaction;
+use solana_transaction_status::UiReturnDataEncoding;
+
+#[derive(Serialize, Debug)]
+pub struct SimulationReport {
+    pub failed: bool,
+    pub errors: Vec<DecodedError>,
+    pub units_consumed: Option<u64>,
+    pub return_data: Option<ReturnData>,
+    pub events: Vec<DecodedEvent>,
+    pub failed_instruction: Option<DecodedInstruction>,
+    pub logs: Vec<String>,
+    pub invocations: Vec<Invocation>,
+    /// See [`crate::log_parser::logs_truncated`].
+    pub logs_truncated: bool,
+}
+
+/// Read a serialized transaction from a file path or an inline string. The
+/// contents may be base64, base58, or (for files) raw wire bytes.
+pub fn read_transaction(input: &str) -> Result<VersionedTransaction> {
+    let path = std::path::Path::new(input);
+    let raw = if path.is_file() {
+        std::fs::read(path)?
+    } else {
+        input.as_bytes().to_vec()
+    };
+
+    let mut candidates = Vec::new();
+    if let Ok(text) = std::str::from_utf8(&raw) {
+        let text = text.trim();
+        if let Ok(bytes) = base64::engine::general_purpose::STANDARD.decode(text) {
+            candidates.push(bytes);
+        }
+        if let Ok(bytes) = bs58::decode(text).into_vec() {
+            candidates.push(bytes);
+        }
+    }
+    candidates.push(raw);
+
+    candidates
+        .iter()
+        .find_map(|bytes| bincode::deserialize::<VersionedTransaction>(bytes).ok())
+        .ok_or_else(|| {
+            OhsolError::InvalidTransaction(
+                "input is not a base64, base58 or binary serialized transaction".to_string(),
+            )
+        })
+}
+
+/// Simulate `transaction` through `decoder`'s RPC URL and decode why it
+/// fails. Signatures are not verified, so unsigned transactions work. With
+/// `replace_blockhash`, the node swaps in a fresh blockhash so stale
+/// transactions can still be simulated.
+pub fn simulate_transaction(
+    decoder: &Decoder,
+    transaction: &VersionedTransaction,
+    replace_blockhash: bool,
+) -> Result<SimulationReport> {
+    let rpc_url = decoder.rpc_url().ok_or(OhsolError::NoRpcUrl)?;
+    let client = rpc::client(rpc_url);
+
+    let result = client
+        .simulate_transaction_with_config(
+            transaction,
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: replace_blockhash,
+                commitment: Some(rpc::commitment()),
+                ..RpcSimulateTransactionConfig::default()
+            },
+        )
+        .map_err(|e| OhsolError::Rpc(format!("Failed to simulate transaction: {}", e)))?
+        .value;
+
+    let logs = result.logs.unwrap_or_default();
+
+    let keys = transaction.message.static_account_keys();
+    let program_ids: Vec<String> = transaction
+        .message
+        .instructions()
+        .iter()
+        .filter_map(|ix| keys.get(ix.program_id_index as usize))
+        .map(|key| key.to_string())
+        .collect();
+
+    let return_data = result.return_data.map(|return_data| {
+        let (data, UiReturnDataEncoding::Base64) = &return_data.data;
+        decode_return_data(
+            &return_data.program_id,
+            data,
+            Some(&transaction.message),
+            decoder,
+        )
+    });
+
+    Ok(SimulationReport {
+        failed: result.err.is_some(),
+        errors: transaction::decode_transaction(&logs, result.err.as_ref(), &program_ids, decoder),
+        units_consumed: result.units_consumed,
+        return_data,
+        events: decode_log_events(&logs, decoder),
+        failed_instruction: result
+            .err
+            .as_ref()
+            .and_then(transaction::failing_instruction_index)
+            .and_then(|index| {
+                // Simulation results don't list loaded addresses, so read the tables.
+                let loaded = rpc::fetch_loaded_addresses(&client, &transaction.message).ok();
+                decode_message_instruction(&transaction.message, loaded.as_ref(), index, decoder)
+            }),
+        invocations: build_invocation_tree(&logs),
+        logs_truncated: logs_truncated(&logs),
+        logs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::message::{Message, VersionedMessage};
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::Signature;
+
+    #[test]
+    fn test_read_transaction_encodings() {
+        let payer = Pubkey::new_unique();
+        let message = Message::new_with_blockhash(
+            &[Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[1, 2, 3],
+                vec![AccountMeta::new(payer, true)],
+            )],
+            Some(&payer),
+            &Hash::default(),
+        );
+        let tx = VersionedTransaction {
+            signatures: vec![Signature::default()],
+            message: VersionedMessage::Legacy(message),
+        };
+        let bytes = bincode::serialize(&tx).unwrap();
+
+        let from_base64 =
+            read_transaction(&base64::engine::general_purpose::STANDARD.encode(&bytes)).unwrap();
+        assert_eq!(from_base64, tx);
+
+        let from_base58 = read_transaction(&bs58::encode(&bytes).into_string()).unwrap();
+        assert_eq!(from_base58, tx);
+
+        assert!(read_transaction("not a transaction!").is_err());
+    }
+}
@@ -0,0 +1,250 @@
+use crate::compute::ComputeReport;
+use crate::decoder::{runtime, Decoder};
+use crate::events::DecodedEvent;
+use crate::instruction::DecodedInstruction;
+use crate::invocation::{build_invocation_tree, Invocation};
+use crate::log_parser::{walk_logs, LogEntry};
+use crate::return_data::ReturnData;
+use crate::DecodedError;
+use serde::Serialize;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::transaction::TransactionError;
+
+/// Everything ohsol extracts from a transaction.
+#[derive(Serialize, Debug, Default)]
+pub struct TransactionReport {
+    pub signature: String,
+    pub errors: Vec<DecodedError>,
+    /// Events logged before the transaction finished or failed.
+    pub events: Vec<DecodedEvent>,
+    /// The top-level instruction named by `InstructionError`, decoded.
+    pub failed_instruction: Option<DecodedInstruction>,
+    pub invocations: Vec<Invocation>,
+    pub compute: ComputeReport,
+    /// Data the transaction's last `set_return_data` call left.
+    pub return_data: Option<ReturnData>,
+    /// See [`crate::log_parser::logs_truncated`].
+    pub logs_truncated: bool,
+}
+
+/// Index of the top-level instruction a transaction error names, if any.
+pub fn failing_instruction_index(err: &TransactionError) -> Option<u8> {
+    match err {
+        TransactionError::InstructionError(index, _) => Some(*index),
+        _ => None,
+    }
+}
+
+/// Decode a transaction's errors from its logs and `err`, however they were
+/// obtained (landed, simulated or streamed).
+///
+/// `program_ids` lists the program of each top-level instruction; when the
+/// message isn't available, pass an empty slice and they are recovered from the
+/// logs instead. If `decoder` has an RPC URL, IDLs of programs missing from its
+/// registry are fetched on demand; otherwise decoding stays offline.
+pub fn decode_transaction(
+    logs: &[String],
+    err: Option<&TransactionError>,
+    program_ids: &[String],
+    decoder: &Decoder,
+) -> Vec<DecodedError> {
+    let mut errors = decode_log_errors(logs, decoder);
+
+    if let Some(err) = err {
+        if program_ids.is_empty() {
+            let program_ids: Vec<String> = build_invocation_tree(logs)
+                .into_iter()
+                .map(|invocation| invocation.program_id)
+                .collect();
+            merge_transaction_error(&mut errors, err, &program_ids, decoder);
+        } else {
+            merge_transaction_error(&mut errors, err, program_ids, decoder);
+        }
+    }
+
+    errors
+}
+
+/// Extract and decode every error reported in a transaction's log messages.
+pub fn decode_log_errors(logs: &[String], decoder: &Decoder) -> Vec<DecodedError> {
+    let mut errors = Vec::new();
+
+    for (frame, entry) in walk_logs(logs) {
+        let current_program = frame.program_id;
+        let current_depth = frame.cpi_depth;
+        let instruction_index = frame.instruction_index;
+
+        match entry {
+            LogEntry::AnchorError {
+                file,
+                line,
+                account,
+                code_name,
+                code_number,
+                message,
+                left,
+                right,
+            } => {
+                let mut error = DecodedError::new(code_number)
+                    .with_name(code_name)
+                    .with_message(message)
+                    .with_compared_values(left, right)
+                    .with_cpi_depth(current_depth)
+                    .with_instruction_index(instruction_index);
+
+                if let (Some(file), Some(line)) = (file, line) {
+                    error = error.with_source(file, line);
+                }
+
+                if let Some(account) = account {
+                    error = error.with_account(account);
+                }
+
+                if let Some(ref pid) = current_program {
+                    error = error.with_program(pid.clone());
+                }
+
+                errors.push(error);
+            }
+            LogEntry::AnchorProgramError {
+                file,
+                line,
+                account,
+                code_name,
+                code_number,
+                message,
+            } => {
+                // `ProgramError::Custom(n)` is logged with `n` as its number.
+                let custom_code = code_name
+                    .starts_with("Custom(")
+                    .then(|| u32::try_from(code_number).ok())
+                    .flatten();
+
+                let mut error = match custom_code {
+                    Some(code) => decoder.decode(code, current_program.as_deref()),
+                    None => runtime::decode_error_name(&code_name)
+                        .unwrap_or_else(|| DecodedError::new(0).with_name(code_name))
+                        .with_message(message),
+                };
+
+                error = error
+                    .with_cpi_depth(current_depth)
+                    .with_instruction_index(instruction_index);
+
+                if let (Some(file), Some(line)) = (file, line) {
+                    error = error.with_source(file, line);
+                }
+
+                if let Some(account) = account {
+                    error = error.with_account(account);
+                }
+
+                if error.program_id.is_empty() {
+                    if let Some(ref pid) = current_program {
+                        error = error.with_program(pid.clone());
+                    }
+                }
+
+                errors.push(error);
+            }
+            LogEntry::CustomError { hex_code } => {
+                let code = u32::from_str_radix(&hex_code, 16).unwrap_or(0);
+                let mut error = decoder.decode(code, current_program.as_deref());
+                error = error
+                    .with_cpi_depth(current_depth)
+                    .with_instruction_index(instruction_index);
+                errors.push(error);
+            }
+            LogEntry::Failed {
+                program_id,
+                error: error_msg,
+            } => {
+                // Extract error code from the error message if it's a custom error
+                if let Some(hex_code) = error_msg.strip_prefix("custom program error: 0x") {
+                    let code = u32::from_str_radix(hex_code, 16).unwrap_or(0);
+                    let mut error = decoder.decode(code, Some(&program_id));
+                    error = error
+                        .with_cpi_depth(current_depth)
+                        .with_instruction_index(instruction_index);
+                    errors.push(error);
+                } else if let Some(error) = runtime::decode_failure_message(&error_msg) {
+                    errors.push(
+                        error
+                            .with_program(program_id)
+                            .with_cpi_depth(current_depth)
+                            .with_instruction_index(instruction_index),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    errors
+}
+
+/// Fold the transaction's `meta.err` into the errors found in its logs.
+///
+/// Execution stops at the first failing instruction, so every log-derived error
+/// belongs to the instruction named by `InstructionError`. The meta error is
+/// only added as its own entry when the logs didn't already report it, e.g.
+/// built-in runtime errors or failures before any program ran.
+pub fn merge_transaction_error(
+    errors: &mut Vec<DecodedError>,
+    err: &TransactionError,
+    program_ids: &[String],
+    decoder: &Decoder,
+) {
+    let decoded = runtime::decode_transaction_error(err, program_ids, decoder);
+
+    if let Some(index) = decoded.instruction_index {
+        for error in errors.iter_mut() {
+            error.instruction_index.get_or_insert(index);
+        }
+    }
+
+    let already_reported = errors.iter().any(|e| match err {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => {
+            e.error_code == *code
+        }
+        _ => e.error_name.is_some() && e.error_name == decoded.error_name,
+    });
+
+    if !already_reported {
+        errors.push(decoded);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_log_errors_restores_parent_after_cpi() {
+        let logs: Vec<String> = [
+            "Program JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4 invoke [1]",
+            "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA invoke [2]",
+            "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA success",
+            "Program log: custom program error: 0x1771",
+            "Program JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4 failed: custom program error: 0x1771",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+
+        let errors = decode_log_errors(&logs, &Decoder::new());
+        assert_eq!(errors.len(), 2);
+        for error in &errors {
+            assert_eq!(
+                error.program_id,
+                "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4"
+            );
+            assert_eq!(
+                error.error_name.as_deref(),
+                Some("SlippageToleranceExceeded")
+            );
+            assert_eq!(error.cpi_depth, Some(1));
+            assert_eq!(error.instruction_index, Some(0));
+        }
+    }
+}
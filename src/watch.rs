@@ -0,0 +1,282 @@
+use crate::decoder::Decoder;
+use crate::log_parser;
+use crate::transaction;
+use crate::DecodedError;
+use crate::{OhsolError, Result};
+use serde::Serialize;
+use solana_client::pubsub_client::PubsubClient;
+use solana_client::rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter};
+use solana_sdk::transaction::TransactionError;
+use std::collections::{HashSet, VecDeque};
+
+/// A transaction seen on the `logsSubscribe` stream, decoded.
+#[derive(Serialize, Debug)]
+pub struct WatchedTransaction {
+    pub signature: String,
+    pub slot: u64,
+    pub failed: bool,
+    pub errors: Vec<DecodedError>,
+    /// See [`crate::log_parser::logs_truncated`].
+    pub logs_truncated: bool,
+}
+
+/// Derive the PubSub WebSocket URL for an RPC endpoint the way the Solana CLI
+/// does: swap the scheme and, if a port is given, use the next one up
+/// (`http://127.0.0.1:8899` -> `ws://127.0.0.1:8900`).
+pub fn ws_url_from_rpc(rpc_url: &str) -> String {
+    let (scheme, rest) = match rpc_url.split_once("://") {
+        Some(("https", rest)) => ("wss", rest),
+        Some((_, rest)) => ("ws", rest),
+        None => ("ws", rpc_url),
+    };
+
+    let (authority, path) = match rest.find('/') {
+        Some(pos) => rest.split_at(pos),
+        None => (rest, ""),
+    };
+
+    let authority = match authority.rsplit_once(':') {
+        Some((host, port)) => match port.parse::<u16>() {
+            Ok(port) => format!("{}:{}", host, port.saturating_add(1)),
+            Err(_) => authority.to_string(),
+        },
+        None => authority.to_string(),
+    };
+
+    format!("{}://{}{}", scheme, authority, path)
+}
+
+/// Decode one `logsNotification`. The notification carries no message, so the
+/// top-level programs are recovered from the logs.
+pub fn decode_notification(
+    signature: String,
+    slot: u64,
+    err: Option<&TransactionError>,
+    logs: &[String],
+    decoder: &Decoder,
+) -> WatchedTransaction {
+    WatchedTransaction {
+        signature,
+        slot,
+        failed: err.is_some(),
+        errors: transaction::decode_transaction(logs, err, &[], decoder),
+        logs_truncated: log_parser::logs_truncated(logs),
+    }
+}
+
+/// How many recent signatures are remembered to drop repeated notifications.
+/// Providers resend within seconds, so this covers far more than needed.
+const RECENT_SIGNATURES: usize = 10_000;
+
+/// The last [`RECENT_SIGNATURES`] signatures seen, oldest evicted first.
+#[derive(Default)]
+struct RecentSignatures {
+    set: HashSet<String>,
+    order: VecDeque<String>,
+}
+
+impl RecentSignatures {
+    /// Record `signature`; false if it is already among the recent ones.
+    fn insert(&mut self, signature: &str) -> bool {
+        if self.set.contains(signature) {
+            return false;
+        }
+        if self.order.len() == RECENT_SIGNATURES {
+            if let Some(oldest) = self.order.pop_front() {
+                self.set.remove(&oldest);
+            }
+        }
+        self.set.insert(signature.to_string());
+        self.order.push_back(signature.to_string());
+        true
+    }
+}
+
+/// Subscribe to logs mentioning `program_id` and call `on_transaction` for each
+/// transaction, until it returns `false` or the connection closes.
+pub fn watch_program<F>(
+    ws_url: &str,
+    decoder: &Decoder,
+    program_id: &str,
+    mut on_transaction: F,
+) -> Result<()>
+where
+    F: FnMut(&WatchedTransaction) -> bool,
+{
+    let (subscription, receiver) = PubsubClient::logs_subscribe(
+        ws_url,
+        RpcTransactionLogsFilter::Mentions(vec![program_id.to_string()]),
+        RpcTransactionLogsConfig {
+            commitment: Some(crate::rpc::commitment()),
+        },
+    )
+    .map_err(|e| OhsolError::Rpc(format!("Failed to subscribe to logs at {}: {}", ws_url, e)))?;
+
+    let mut seen = RecentSignatures::default();
+
+    for notification in receiver.iter() {
+        let value = notification.value;
+
+        // Some providers deliver the same transaction more than once.
+        if !seen.insert(&value.signature) {
+            continue;
+        }
+
+        let tx = decode_notification(
+            value.signature,
+            notification.context.slot,
+            value.err.as_ref(),
+            &value.logs,
+            decoder,
+        );
+
+        if !on_transaction(&tx) {
+            break;
+        }
+    }
+
+    // The reader thread holds the socket until the next message arrives, and
+    // unsubscribing waits for it, which could take forever on a quiet
+    // program. Unsubscribe in the background instead; closing the socket then
+    // ends the reader.
+    drop(receiver);
+    std::thread::spawn(move || drop(subscription));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use tungstenite::Message;
+
+    #[test]
+    fn test_ws_url_from_rpc() {
+        assert_eq!(
+            ws_url_from_rpc("http://127.0.0.1:8899"),
+            "ws://127.0.0.1:8900"
+        );
+        assert_eq!(
+            ws_url_from_rpc("https://api.devnet.solana.com"),
+            "wss://api.devnet.solana.com"
+        );
+        assert_eq!(
+            ws_url_from_rpc("https://rpc.example.com/key/abc"),
+            "wss://rpc.example.com/key/abc"
+        );
+    }
+
+    #[test]
+    fn test_recent_signatures_evict_oldest() {
+        let mut seen = RecentSignatures::default();
+        assert!(seen.insert("sig0"));
+        assert!(!seen.insert("sig0"));
+
+        for i in 1..=RECENT_SIGNATURES {
+            assert!(seen.insert(&format!("sig{}", i)));
+        }
+        assert_eq!(seen.set.len(), RECENT_SIGNATURES);
+        assert!(seen.insert("sig0"));
+        assert!(!seen.insert(&format!("sig{}", RECENT_SIGNATURES)));
+    }
+
+    /// Minimal stand-in for a validator's PubSub endpoint: acknowledges the
+    /// subscription, replays the given notifications, then hangs up (or, with
+    /// `stay_open`, goes quiet).
+    fn serve_notifications(values: Vec<serde_json::Value>, stay_open: bool) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut socket = tungstenite::accept(stream).unwrap();
+
+            let request = socket.read().unwrap().into_text().unwrap();
+            assert!(request.contains("logsSubscribe"));
+            socket
+                .send(Message::Text(
+                    r#"{"jsonrpc":"2.0","result":7,"id":1}"#.to_string(),
+                ))
+                .unwrap();
+
+            for (slot, value) in values.into_iter().enumerate() {
+                let notification = serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "method": "logsNotification",
+                    "params": {
+                        "result": { "context": { "slot": slot }, "value": value },
+                        "subscription": 7
+                    }
+                });
+                socket
+                    .send(Message::Text(notification.to_string()))
+                    .unwrap();
+            }
+
+            if stay_open {
+                std::thread::sleep(std::time::Duration::from_secs(60));
+            }
+            socket.close(None).ok();
+        });
+
+        format!("ws://{}", addr)
+    }
+
+    #[test]
+    fn test_watch_decodes_failures() {
+        let jupiter = "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4";
+        let failed = serde_json::json!({
+            "signature": "sig1",
+            "err": { "InstructionError": [0, { "Custom": 6001 }] },
+            "logs": [
+                format!("Program {} invoke [1]", jupiter),
+                format!("Program {} failed: custom program error: 0x1771", jupiter),
+            ]
+        });
+        let succeeded = serde_json::json!({
+            "signature": "sig2",
+            "err": null,
+            "logs": [
+                format!("Program {} invoke [1]", jupiter),
+                format!("Program {} success", jupiter),
+            ]
+        });
+
+        let ws_url = serve_notifications(vec![failed.clone(), failed, succeeded], false);
+
+        let mut seen = Vec::new();
+        watch_program(&ws_url, &Decoder::new(), jupiter, |tx| {
+            seen.push((tx.signature.clone(), tx.failed, tx.errors.len()));
+            seen.len() < 2
+        })
+        .unwrap();
+
+        assert_eq!(
+            seen,
+            vec![
+                ("sig1".to_string(), true, 1),
+                ("sig2".to_string(), false, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_watch_returns_on_a_quiet_stream() {
+        let jupiter = "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4";
+        let value = serde_json::json!({ "signature": "sig1", "err": null, "logs": [] });
+        let ws_url = serve_notifications(vec![value], true);
+
+        let (done, finished) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            done.send(watch_program(&ws_url, &Decoder::new(), jupiter, |_| false))
+                .ok();
+        });
+        let result = finished.recv_timeout(std::time::Duration::from_secs(10));
+        assert!(
+            matches!(result, Ok(Ok(()))),
+            "watch_program didn't return: {:?}",
+            result
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use crate::decoder::registry::Registry;
+use crate::idl::{cache, parse_idl, Idl};
+use crate::network::{url_or_moniker, Cluster};
+use crate::{OhsolError, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+const MANIFEST: &str = "Anchor.toml";
+
+#[derive(Deserialize, Debug, Default)]
+struct Provider {
+    #[serde(default)]
+    cluster: String,
+}
+
+/// A `[programs.<cluster>]` entry: either just the address, or a table with
+/// an explicit IDL path as older Anchor versions allowed.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum ProgramEntry {
+    Address(String),
+    Detailed {
+        address: String,
+        idl: Option<PathBuf>,
+    },
+}
+
+impl ProgramEntry {
+    fn address(&self) -> &str {
+        match self {
+            ProgramEntry::Address(address) => address,
+            ProgramEntry::Detailed { address, .. } => address,
+        }
+    }
+}
+
+/// The parts of `Anchor.toml` ohsol cares about.
+#[derive(Deserialize, Debug, Default)]
+struct AnchorManifest {
+    #[serde(default)]
+    provider: Provider,
+    #[serde(default)]
+    programs: HashMap<String, HashMap<String, ProgramEntry>>,
+}
+
+/// A program built in the workspace, with the address it is deployed at on
+/// the selected cluster.
+#[derive(Debug)]
+pub struct WorkspaceProgram {
+    pub name: String,
+    pub program_id: String,
+    pub idl: Idl,
+}
+
+/// A local Anchor workspace: the directory containing `Anchor.toml`.
+#[derive(Debug)]
+pub struct Workspace {
+    root: PathBuf,
+    manifest: AnchorManifest,
+}
+
+impl Workspace {
+    pub fn open(root: &Path) -> Result<Self> {
+        let path = root.join(MANIFEST);
+        let data =
+            std::fs::read_to_string(&path).map_err(|e| OhsolError::invalid_file(&path, e))?;
+        let manifest = toml::from_str(&data).map_err(|e| OhsolError::invalid_file(&path, e))?;
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            manifest,
+        })
+    }
+
+    /// The `[programs.*]` table for `network`. Without a network, the
+    /// provider's cluster is used, else `localnet`.
+    fn cluster_programs(&self, network: Option<&str>) -> Option<&HashMap<String, ProgramEntry>> {
+        let programs = &self.manifest.programs;
+        match network {
+            Some(network) => programs.get(network),
+            None => {
+                let provider =
+                    cache::network_name(&url_or_moniker(&self.manifest.provider.cluster));
+                programs.get(&provider).or_else(|| programs.get("localnet"))
+            }
+        }
+    }
+
+    /// Load the IDL of every program built under `target/idl`. Program IDs
+    /// come from `Anchor.toml` for `network` (see [`cache::network_name`]),
+    /// falling back to the address recorded in the IDL itself. A network that
+    /// is neither a known cluster nor has its own table yields no programs.
+    pub fn programs(&self, network: Option<&str>) -> Result<Vec<WorkspaceProgram>> {
+        let addresses = self.cluster_programs(network);
+        // A custom endpoint could be any cluster, so any address would be a guess.
+        if addresses.is_none() && network.is_some_and(|n| n.parse::<Cluster>().is_err()) {
+            return Ok(Vec::new());
+        }
+
+        let mut idl_files: HashMap<String, PathBuf> = HashMap::new();
+        if let Ok(entries) = std::fs::read_dir(self.root.join("target").join("idl")) {
+            for path in entries.filter_map(|e| e.ok().map(|e| e.path())) {
+                if path.extension().is_some_and(|ext| ext == "json") {
+                    if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                        idl_files.insert(stem.to_string(), path.clone());
+                    }
+                }
+            }
+        }
+        for (name, entry) in addresses.into_iter().flatten() {
+            if let ProgramEntry::Detailed { idl: Some(idl), .. } = entry {
+                idl_files.insert(name.clone(), self.root.join(idl));
+            }
+        }
+
+        let mut programs = Vec::new();
+        for (name, path) in idl_files {
+            let json =
+                std::fs::read_to_string(&path).map_err(|e| OhsolError::invalid_file(&path, e))?;
+            let idl = parse_idl(&json).map_err(|e| OhsolError::invalid_file(&path, e))?;
+
+            let program_id = addresses
+                .and_then(|a| a.get(&name))
+                .map(|entry| entry.address().to_string())
+                .or_else(|| idl.program_id().map(str::to_string));
+
+            if let Some(program_id) = program_id {
+                programs.push(WorkspaceProgram {
+                    name,
+                    program_id,
+                    idl,
+                });
+            }
+        }
+
+        programs.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(programs)
+    }
+}
+
+/// Find the Anchor workspace containing `start` by walking up to the nearest
+/// `Anchor.toml`.
+pub fn find_workspace(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .find(|dir| dir.join(MANIFEST).is_file())
+        .map(Path::to_path_buf)
+}
+
+/// Register the errors of every program in the workspace at `root` with
+/// `registry`, using the program IDs for the cluster `rpc_url` points at (or
+/// the provider's cluster without one). See [`Workspace::programs`].
+pub fn register_workspace(
+    root: &Path,
+    rpc_url: Option<&str>,
+    registry: &Registry,
+) -> Result<Vec<WorkspaceProgram>> {
+    let workspace = Workspace::open(root)?;
+    let programs = workspace.programs(rpc_url.map(cache::network_name).as_deref())?;
+
+    for program in &programs {
+        registry.register_idl_errors(&program.program_id, &program.idl);
+        registry.store_idl(&program.program_id, program.idl.clone());
+    }
+
+    Ok(programs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_workspace() {
+        let root = std::env::temp_dir().join(format!("ohsol-workspace-{}", std::process::id()));
+        let idl_dir = root.join("target").join("idl");
+        std::fs::create_dir_all(&idl_dir).unwrap();
+        std::fs::create_dir_all(root.join("programs").join("escrow")).unwrap();
+
+        std::fs::write(
+            root.join("Anchor.toml"),
+            r#"
+[provider]
+cluster = "Localnet"
+wallet = "~/.config/solana/id.json"
+
+[programs.localnet]
+escrow = "6eEnXpgRkF5WnBW8Kn5k8SYcsbX5FxngGjMzKPEVeiBe"
+
+[programs.devnet]
+escrow = "8Yhu2FdfYgnVx8okmc1CwAqrsnbu5hhUsPAd4HBiHSFH"
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            idl_dir.join("escrow.json"),
+            r#"{
+                "address": "6eEnXpgRkF5WnBW8Kn5k8SYcsbX5FxngGjMzKPEVeiBe",
+                "metadata": { "name": "escrow", "version": "0.1.0", "spec": "0.1.0" },
+                "errors": [{ "code": 6000, "name": "OfferExpired", "msg": "Offer expired" }]
+            }"#,
+        )
+        .unwrap();
+
+        let found = find_workspace(&root.join("programs").join("escrow")).unwrap();
+        assert_eq!(found, root);
+
+        let registry = Registry::builtin();
+        let programs =
+            register_workspace(&found, Some("https://api.devnet.solana.com"), &registry).unwrap();
+
+        assert_eq!(programs.len(), 1);
+        let devnet_id = "8Yhu2FdfYgnVx8okmc1CwAqrsnbu5hhUsPAd4HBiHSFH";
+        assert_eq!(programs[0].program_id, devnet_id);
+        let error = registry.lookup_program_error(devnet_id, 6000).unwrap();
+        assert_eq!(error.error_name.as_deref(), Some("OfferExpired"));
+
+        // No [programs.mainnet]: the address in the IDL.
+        let workspace = Workspace::open(&root).unwrap();
+        let mainnet = workspace.programs(Some("mainnet")).unwrap();
+        assert_eq!(
+            mainnet[0].program_id,
+            "6eEnXpgRkF5WnBW8Kn5k8SYcsbX5FxngGjMzKPEVeiBe"
+        );
+        // Offline: the provider's cluster.
+        let offline = workspace.programs(None).unwrap();
+        assert_eq!(
+            offline[0].program_id,
+            "6eEnXpgRkF5WnBW8Kn5k8SYcsbX5FxngGjMzKPEVeiBe"
+        );
+        // A host that names no cluster gets no guessed addresses.
+        let custom = Registry::builtin();
+        assert!(
+            register_workspace(&found, Some("https://rpc.example.com"), &custom)
+                .unwrap()
+                .is_empty()
+        );
+        assert!(!custom.has_program(devnet_id));
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+}